@@ -0,0 +1,211 @@
+//! Folding benchmarks over the example circuits, measuring every stage of the
+//! pipeline separately. Circuits whose circom artifacts have not been compiled
+//! (see `examples/*/compile.sh`) are skipped.
+use std::{collections::HashMap, env::temp_dir, path::PathBuf};
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use ff::PrimeField;
+use nova_scotia::{
+    circom::{
+        circuit::CircomCircuit,
+        reader::{generate_witness_from_bin, load_r1cs},
+    },
+    compress_recursive_snark, create_public_params,
+    verify::{to_decimal_strings, verify_compressed_proof, verify_recursive_proof},
+    FileLocation, F1, G2,
+};
+use nova_snark::{
+    traits::{circuit::TrivialTestCircuit, Group},
+    RecursiveSNARK,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+struct BenchCircuit {
+    name: &'static str,
+    r1cs: PathBuf,
+    witness_generator: PathBuf,
+    start_public_input: Vec<F1>,
+    /// private inputs of the two steps that are proved
+    private_inputs: [HashMap<String, Value>; 2],
+}
+
+fn example_path(path: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("examples")
+        .join(path)
+}
+
+fn toy() -> BenchCircuit {
+    let private_input = |adder: u64| HashMap::from([("adder".to_string(), json!(adder))]);
+    BenchCircuit {
+        name: "toy",
+        r1cs: example_path("toy/toy.r1cs"),
+        witness_generator: example_path("toy/toy_cpp/toy"),
+        start_public_input: vec![F1::from(10), F1::from(10)],
+        private_inputs: [private_input(0), private_input(1)],
+    }
+}
+
+#[derive(Deserialize)]
+#[allow(non_snake_case)]
+struct Blocks {
+    prevBlockHash: [String; 2],
+    blockHashes: Vec<[String; 2]>,
+    blockHeaders: Vec<Vec<u8>>,
+}
+
+fn bitcoin() -> BenchCircuit {
+    // matches `Main(5)` in bitcoin_benchmark.circom
+    let per_iteration_count = 5;
+    let btc_blocks: Blocks =
+        serde_json::from_str(include_str!("../examples/bitcoin/fetcher/btc-blocks.json")).unwrap();
+    let private_input = |i: usize| {
+        let blocks = i * per_iteration_count..(i + 1) * per_iteration_count;
+        HashMap::from([
+            (
+                "blockHashes".to_string(),
+                json!(btc_blocks.blockHashes[blocks.clone()]),
+            ),
+            (
+                "blockHeaders".to_string(),
+                json!(btc_blocks.blockHeaders[blocks]),
+            ),
+        ])
+    };
+    BenchCircuit {
+        name: "bitcoin",
+        r1cs: example_path("bitcoin/circom/bitcoin_benchmark.r1cs"),
+        witness_generator: example_path("bitcoin/circom/bitcoin_benchmark_cpp/bitcoin_benchmark"),
+        start_public_input: vec![
+            F1::from_str_vartime(&btc_blocks.prevBlockHash[0]).unwrap(),
+            F1::from_str_vartime(&btc_blocks.prevBlockHash[1]).unwrap(),
+        ],
+        private_inputs: [private_input(0), private_input(1)],
+    }
+}
+
+fn input_json(step_in: &[F1], private_input: &HashMap<String, Value>) -> String {
+    let mut input = private_input.clone();
+    input.insert("step_in".to_string(), json!(to_decimal_strings(step_in)));
+    serde_json::to_string(&input).unwrap()
+}
+
+fn bench_circuit(c: &mut Criterion, circuit: BenchCircuit) {
+    if !circuit.r1cs.exists() || !circuit.witness_generator.exists() {
+        eprintln!(
+            "skipping {} benchmarks, compile its circom artifacts first",
+            circuit.name
+        );
+        return;
+    }
+
+    let mut group = c.benchmark_group(circuit.name);
+    group.sample_size(10);
+
+    let r1cs_file = FileLocation::PathBuf(circuit.r1cs.clone());
+    group.bench_function("load_r1cs", |b| b.iter(|| load_r1cs(&r1cs_file)));
+    let r1cs = load_r1cs(&r1cs_file);
+
+    let witness_output = temp_dir().join(format!("nova_scotia_bench_{}.wtns", circuit.name));
+    let first_input = input_json(&circuit.start_public_input, &circuit.private_inputs[0]);
+    group.bench_function("generate_witness", |b| {
+        b.iter(|| {
+            generate_witness_from_bin::<F1>(
+                &circuit.witness_generator,
+                &first_input,
+                &witness_output,
+            )
+            .unwrap()
+        })
+    });
+
+    group.bench_function("create_public_params", |b| {
+        b.iter(|| create_public_params(r1cs.clone()))
+    });
+    let pp = create_public_params(r1cs.clone());
+
+    let mut step_in = circuit.start_public_input.clone();
+    let mut circuits = Vec::new();
+    for private_input in &circuit.private_inputs {
+        let witness = generate_witness_from_bin::<F1>(
+            &circuit.witness_generator,
+            &input_json(&step_in, private_input),
+            &witness_output,
+        )
+        .unwrap();
+        let step = CircomCircuit {
+            r1cs: r1cs.clone(),
+            witness: Some(r1cs.map_witness(witness)),
+        };
+        step_in = step.get_public_outputs();
+        circuits.push(step);
+    }
+    let _ = std::fs::remove_file(&witness_output);
+
+    let z0_secondary = vec![<G2 as Group>::Scalar::zero()];
+    let prove_step = |previous, step: &CircomCircuit<F1>| {
+        RecursiveSNARK::prove_step(
+            &pp,
+            previous,
+            step.clone(),
+            TrivialTestCircuit::default(),
+            circuit.start_public_input.clone(),
+            z0_secondary.clone(),
+        )
+        .unwrap()
+    };
+
+    // the first step is a special case in Nova, so measure folding the second
+    let first_snark = prove_step(None, &circuits[0]);
+    group.bench_function("prove_step", |b| {
+        b.iter_batched(
+            || first_snark.clone(),
+            |snark| prove_step(Some(snark), &circuits[1]),
+            BatchSize::LargeInput,
+        )
+    });
+    let recursive_snark = prove_step(Some(first_snark), &circuits[1]);
+
+    group.bench_function("verify", |b| {
+        b.iter(|| {
+            verify_recursive_proof(
+                &pp,
+                &recursive_snark,
+                circuits.len(),
+                circuit.start_public_input.clone(),
+            )
+            .unwrap()
+        })
+    });
+
+    group.bench_function("compress", |b| {
+        b.iter(|| compress_recursive_snark(&pp, &recursive_snark).unwrap())
+    });
+    let compressed_snark = compress_recursive_snark(&pp, &recursive_snark).unwrap();
+
+    group.bench_function("verify_compressed", |b| {
+        b.iter(|| {
+            verify_compressed_proof(
+                &pp,
+                &compressed_snark,
+                circuits.len(),
+                circuit.start_public_input.clone(),
+            )
+            .unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_toy(c: &mut Criterion) {
+    bench_circuit(c, toy());
+}
+
+fn bench_bitcoin(c: &mut Criterion) {
+    bench_circuit(c, bitcoin());
+}
+
+criterion_group!(benches, bench_toy, bench_bitcoin);
+criterion_main!(benches);
@@ -1,14 +1,17 @@
 use std::collections::HashMap;
 
+use js_sys::Uint8Array;
 use nova_scotia::FileLocation;
 use nova_scotia::{
-    circom::{circuit::CircomCircuit, reader::load_r1cs},
-    create_public_params, create_recursive_circuit, EE1, EE2, F1, F2, G1, G2, S1, S2,
+    circom::{
+        circuit::{CircomCircuit, R1CS},
+        reader::load_r1cs,
+    },
+    create_public_params, create_recursive_circuit, public_params_from_bytes, verify, EE1, EE2, F1,
+    F2, G1, G2, S1, S2,
 };
 use nova_snark::{
-    spartan::RelaxedR1CSSNARK,
-    traits::{circuit::TrivialTestCircuit, Group},
-    CompressedSNARK, PublicParams,
+    spartan::RelaxedR1CSSNARK, traits::circuit::TrivialTestCircuit, CompressedSNARK, PublicParams,
 };
 use serde_json::json;
 use wasm_bindgen::prelude::*;
@@ -57,16 +60,54 @@ pub async fn generate_params() -> String {
     return serialised;
 }
 
+/// Same as `generate_params`, taking the r1cs file's bytes instead of fetching it.
 #[wasm_bindgen]
-pub async fn generate_proof(pp_str: String) -> String {
-    let iteration_count = 5;
+pub async fn generate_params_from_bytes(r1cs: Uint8Array) -> String {
+    let r1cs = load_r1cs(&FileLocation::Bytes(r1cs.to_vec())).await;
+    let pp = create_public_params(r1cs.clone());
+    let serialised = serde_json::to_string(&pp).unwrap();
+    return serialised;
+}
 
+#[wasm_bindgen]
+pub async fn generate_proof(pp_str: String) -> String {
     let r1cs = load_r1cs(&FileLocation::URL(
         "http://localhost:3000/toy.r1cs".to_string(),
     ))
     .await;
     let witness_generator_wasm = FileLocation::URL("http://localhost:3000/toy.wasm".to_string());
 
+    let pp =
+        serde_json::from_str::<PublicParams<G1, G2, CircomCircuit<F1>, TrivialTestCircuit<F2>>>(
+            &pp_str,
+        )
+        .unwrap();
+
+    prove(pp, r1cs, witness_generator_wasm).await
+}
+
+/// Same as `generate_proof`, taking the serialized params, r1cs file and witness
+/// generator wasm as bytes, so apps can cache or bundle them instead of fetching.
+#[wasm_bindgen]
+pub async fn generate_proof_from_bytes(
+    pp: Uint8Array,
+    r1cs: Uint8Array,
+    witness_generator_wasm: Uint8Array,
+) -> String {
+    let pp = public_params_from_bytes(&pp.to_vec()).unwrap();
+    let r1cs = load_r1cs(&FileLocation::Bytes(r1cs.to_vec())).await;
+    let witness_generator_wasm = FileLocation::Bytes(witness_generator_wasm.to_vec());
+
+    prove(pp, r1cs, witness_generator_wasm).await
+}
+
+async fn prove(
+    pp: PublicParams<G1, G2, CircomCircuit<F1>, TrivialTestCircuit<F2>>,
+    r1cs: R1CS<F1>,
+    witness_generator_wasm: FileLocation,
+) -> String {
+    let iteration_count = 5;
+
     let mut private_inputs = Vec::new();
     for i in 0..iteration_count {
         let mut private_input = HashMap::new();
@@ -76,12 +117,6 @@ pub async fn generate_proof(pp_str: String) -> String {
 
     let start_public_input = vec![F1::from(10), F1::from(10)];
 
-    let pp =
-        serde_json::from_str::<PublicParams<G1, G2, CircomCircuit<F1>, TrivialTestCircuit<F2>>>(
-            &pp_str,
-        )
-        .unwrap();
-
     console_log!(
         "Number of constraints per step (primary circuit): {}",
         pp.num_constraints().0
@@ -111,16 +146,13 @@ pub async fn generate_proof(pp_str: String) -> String {
     .await
     .unwrap();
 
-    // TODO: empty?
-    let z0_secondary = vec![<G2 as Group>::Scalar::zero()];
-
     // verify the recursive SNARK
     console_log!("Verifying a RecursiveSNARK...");
-    let res = recursive_snark.verify(
+    let res = verify::verify_recursive_proof(
         &pp,
+        &recursive_snark,
         iteration_count,
         start_public_input.clone(),
-        z0_secondary.clone(),
     );
     assert!(res.is_ok());
 
@@ -142,7 +174,6 @@ pub async fn verify_compressed_proof(pp_str: String, proof_str: String) -> bool
 
     let iteration_count = 5;
     let start_public_input = vec![F1::from(10), F1::from(10)];
-    let z0_secondary = vec![<G2 as Group>::Scalar::zero()];
 
     let compressed_proof = serde_json::from_str::<
         CompressedSNARK<
@@ -155,11 +186,11 @@ pub async fn verify_compressed_proof(pp_str: String, proof_str: String) -> bool
         >,
     >(&proof_str)
     .unwrap();
-    let res = compressed_proof.verify(
+    let res = verify::verify_compressed_proof(
         &pp,
+        &compressed_proof,
         iteration_count,
         start_public_input.clone(),
-        z0_secondary,
     );
     return res.is_ok();
 }
@@ -1,7 +1,8 @@
 import React, { useState } from "react";
 import logo from "./logo.svg";
 import "./App.css";
-import { wrap } from "comlink";
+import { proxy, wrap } from "comlink";
+import type { ProofProgress } from "./nova-scotia-worker";
 
 function App() {
   const worker = new Worker(new URL("./nova-scotia-worker", import.meta.url), {
@@ -16,6 +17,7 @@ function App() {
   const [paramTime, setParamTime] = useState(-1);
   const [proofTime, setProofTime] = useState(-1);
   const [verifyTime, setVerifyTime] = useState(-1);
+  const [progress, setProgress] = useState<ProofProgress | null>(null);
 
   async function generate_params() {
     setParamTime(0);
@@ -28,10 +30,22 @@ function App() {
   async function generate_proof() {
     setProofTime(0);
     const start2 = performance.now();
-    const proof = await workerApi.generate_proof(pp);
-    console.log("proof time", performance.now() - start2);
-    setProof(proof);
-    setProofTime(performance.now() - start2);
+    try {
+      const proof = await workerApi.generate_proof_with_progress(
+        pp,
+        proxy(setProgress)
+      );
+      console.log("proof time", performance.now() - start2);
+      setProof(proof);
+      setProofTime(performance.now() - start2);
+    } catch (e) {
+      console.log(e);
+      setProofTime(-1);
+    }
+  }
+
+  async function cancel_proof() {
+    await workerApi.cancel_proof();
   }
 
   async function verify_proof() {
@@ -71,6 +85,20 @@ function App() {
                 ? "Running"
                 : (proofTime / 1000).toFixed(2) + "s"}
             </p>
+            {proofTime === 0 && (
+              <>
+                <p>
+                  {progress === null
+                    ? "Starting"
+                    : `Step ${progress.step + 1}/${progress.steps}: ${
+                        progress.kind === "witness"
+                          ? "witness generated"
+                          : "folded"
+                      } in ${progress.elapsedMs.toFixed(0)}ms`}
+                </p>
+                <button onClick={cancel_proof}>Cancel</button>
+              </>
+            )}
           </>
         )}
 
@@ -1,11 +1,29 @@
 import { expose } from "comlink";
+import type { ProofCancellation } from "nova_scotia_browser";
+
+// The circuit artifacts are fetched once and then reused for every call. Apps
+// could equally load them from IndexedDB or bundle them.
+const artifacts: Map<string, Promise<Uint8Array>> = new Map();
+
+function fetch_artifact(path: string): Promise<Uint8Array> {
+  if (!artifacts.has(path)) {
+    artifacts.set(
+      path,
+      fetch(path)
+        .then((response) => response.arrayBuffer())
+        .then((buffer) => new Uint8Array(buffer))
+    );
+  }
+  return artifacts.get(path)!;
+}
 
 async function generate_params() {
   const multiThread = await import("nova_scotia_browser");
   await multiThread.default();
   await multiThread.initThreadPool(navigator.hardwareConcurrency);
 
-  return await multiThread.generate_params();
+  const r1cs = await fetch_artifact("/toy.r1cs");
+  return await multiThread.generate_params_from_bytes(r1cs);
 }
 
 async function generate_proof(pp: string) {
@@ -13,7 +31,60 @@ async function generate_proof(pp: string) {
   await multiThread.default();
   await multiThread.initThreadPool(navigator.hardwareConcurrency);
 
-  return await multiThread.generate_proof(pp);
+  const r1cs = await fetch_artifact("/toy.r1cs");
+  const wasm = await fetch_artifact("/toy.wasm");
+  return await multiThread.generate_proof_from_bytes(
+    new TextEncoder().encode(pp),
+    r1cs,
+    wasm
+  );
+}
+
+export type ProofProgress = {
+  kind: "witness" | "step";
+  step: number;
+  steps: number;
+  elapsedMs: number;
+  output?: string[];
+};
+
+let cancellation: ProofCancellation | undefined;
+
+// Same as generate_proof, but reports every step to `on_progress` and can be
+// stopped with cancel_proof between two steps.
+async function generate_proof_with_progress(
+  pp: string,
+  on_progress: (progress: ProofProgress) => void
+) {
+  const multiThread = await import("nova_scotia_browser");
+  await multiThread.default();
+  await multiThread.initThreadPool(navigator.hardwareConcurrency);
+
+  const r1cs = await fetch_artifact("/toy.r1cs");
+  const wasm = await fetch_artifact("/toy.wasm");
+  const private_inputs = [0, 1, 2, 3, 4].map((i) => ({ adder: i }));
+  const z0 = JSON.stringify(["10", "10"]);
+
+  cancellation = new multiThread.ProofCancellation();
+  try {
+    const recursive_snark = await multiThread.proveWithProgress(
+      pp,
+      r1cs,
+      wasm,
+      z0,
+      JSON.stringify(private_inputs),
+      on_progress,
+      cancellation
+    );
+    return multiThread.compress(pp, recursive_snark);
+  } finally {
+    cancellation.free();
+    cancellation = undefined;
+  }
+}
+
+function cancel_proof() {
+  cancellation?.cancel();
 }
 
 async function verify_proof(pp: string, proof: string) {
@@ -27,6 +98,8 @@ async function verify_proof(pp: string, proof: string) {
 const exports = {
   generate_params,
   generate_proof,
+  generate_proof_with_progress,
+  cancel_proof,
   verify_proof,
 };
 export type NovaScotiaWorker = typeof exports;
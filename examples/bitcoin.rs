@@ -7,7 +7,8 @@ use std::{
 
 use ff::PrimeField;
 use nova_scotia::{
-    circom::reader::load_r1cs, create_public_params, create_recursive_circuit, FileLocation, F1, G2,
+    analysis::CircuitReport, circom::reader::load_r1cs, create_public_params,
+    create_recursive_circuit, FileLocation, F1, G2,
 };
 use nova_snark::traits::Group;
 use serde::{Deserialize, Serialize};
@@ -63,23 +64,7 @@ fn bench(iteration_count: usize, per_iteration_count: usize) -> (Duration, Durat
 
     let pp = create_public_params(r1cs.clone());
 
-    println!(
-        "Number of constraints per step (primary circuit): {}",
-        pp.num_constraints().0
-    );
-    println!(
-        "Number of constraints per step (secondary circuit): {}",
-        pp.num_constraints().1
-    );
-
-    println!(
-        "Number of variables per step (primary circuit): {}",
-        pp.num_variables().0
-    );
-    println!(
-        "Number of variables per step (secondary circuit): {}",
-        pp.num_variables().1
-    );
+    println!("{}", CircuitReport::new(&r1cs, &pp));
 
     println!("Creating a RecursiveSNARK...");
     let start = Instant::now();
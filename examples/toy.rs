@@ -1,13 +1,20 @@
 use std::{collections::HashMap, env::current_dir, time::Instant};
 
 use nova_scotia::{
-    circom::reader::load_r1cs, create_public_params, create_recursive_circuit, FileLocation, F1,
-    G2, S1, S2,
+    analysis::CircuitReport,
+    circom::reader::load_r1cs,
+    compress_recursive_snark, create_public_params, create_recursive_circuit,
+    verify::{to_decimal_strings, verify_compressed_proof, verify_recursive_proof},
+    FileLocation, F1,
 };
-use nova_snark::{traits::Group, CompressedSNARK};
 use serde_json::json;
+use tracing_subscriber::fmt::format::FmtSpan;
 
 fn main() {
+    tracing_subscriber::fmt()
+        .with_span_events(FmtSpan::CLOSE)
+        .init();
+
     let iteration_count = 5;
     let root = current_dir().unwrap();
 
@@ -26,23 +33,7 @@ fn main() {
 
     let pp = create_public_params(r1cs.clone());
 
-    println!(
-        "Number of constraints per step (primary circuit): {}",
-        pp.num_constraints().0
-    );
-    println!(
-        "Number of constraints per step (secondary circuit): {}",
-        pp.num_constraints().1
-    );
-
-    println!(
-        "Number of variables per step (primary circuit): {}",
-        pp.num_variables().0
-    );
-    println!(
-        "Number of variables per step (secondary circuit): {}",
-        pp.num_variables().1
-    );
+    println!("{}", CircuitReport::new(&r1cs, &pp));
 
     println!("Creating a RecursiveSNARK...");
     let start = Instant::now();
@@ -56,21 +47,18 @@ fn main() {
     .unwrap();
     println!("RecursiveSNARK creation took {:?}", start.elapsed());
 
-    // TODO: empty?
-    let z0_secondary = vec![<G2 as Group>::Scalar::zero()];
-
     // verify the recursive SNARK
     println!("Verifying a RecursiveSNARK...");
     let start = Instant::now();
-    let res = recursive_snark.verify(
+    let res = verify_recursive_proof(
         &pp,
+        &recursive_snark,
         iteration_count,
         start_public_input.clone(),
-        z0_secondary.clone(),
     );
     println!(
         "RecursiveSNARK::verify: {:?}, took {:?}",
-        res,
+        res.as_ref().map(|z_n| to_decimal_strings(z_n)),
         start.elapsed()
     );
     assert!(res.is_ok());
@@ -78,7 +66,7 @@ fn main() {
     // produce a compressed SNARK
     println!("Generating a CompressedSNARK using Spartan with IPA-PC...");
     let start = Instant::now();
-    let res = CompressedSNARK::<_, _, _, _, S1, S2>::prove(&pp, &recursive_snark);
+    let res = compress_recursive_snark(&pp, &recursive_snark);
     println!(
         "CompressedSNARK::prove: {:?}, took {:?}",
         res.is_ok(),
@@ -90,11 +78,11 @@ fn main() {
     // verify the compressed SNARK
     println!("Verifying a CompressedSNARK...");
     let start = Instant::now();
-    let res = compressed_snark.verify(
+    let res = verify_compressed_proof(
         &pp,
+        &compressed_snark,
         iteration_count,
         start_public_input.clone(),
-        z0_secondary,
     );
     println!(
         "CompressedSNARK::verify: {:?}, took {:?}",
@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nova_scotia::circom::{file::ParseLimits, reader::try_load_r1cs_from_bytes};
+
+// small enough that a valid-looking file cannot exhaust the fuzzer's memory
+const LIMITS: ParseLimits = ParseLimits {
+    max_wires: 1 << 16,
+    max_constraints: 1 << 16,
+    max_terms: 1 << 18,
+};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = try_load_r1cs_from_bytes(data, &LIMITS);
+});
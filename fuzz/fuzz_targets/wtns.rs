@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nova_scotia::{
+    circom::{file::ParseLimits, reader::load_witness_from_array_with_limits},
+    F1,
+};
+
+const LIMITS: ParseLimits = ParseLimits {
+    max_wires: 1 << 16,
+    max_constraints: 1 << 16,
+    max_terms: 1 << 18,
+};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = load_witness_from_array_with_limits::<F1>(data, &LIMITS);
+});
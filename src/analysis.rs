@@ -0,0 +1,209 @@
+use std::{collections::BTreeMap, fmt};
+
+use ff::PrimeField;
+use nova_snark::PublicParams;
+
+use crate::{circom::circuit::R1CS, C1, C2, G1, G2};
+
+/// size of a serialized field element or scalar, in bytes
+const FIELD_BYTES: usize = 32;
+/// size of an affine commitment key generator, in bytes
+const POINT_BYTES: usize = 2 * FIELD_BYTES;
+/// size of a sparse matrix entry: coefficient plus row and column index
+const ENTRY_BYTES: usize = FIELD_BYTES + 2 * std::mem::size_of::<usize>();
+
+/// Statistics of a circom step circuit, before Nova augments it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct R1CSStats {
+    pub num_constraints: usize,
+    pub num_variables: usize,
+    /// public inputs plus public outputs, excluding the constant one wire
+    pub num_public: usize,
+    /// length of the folded state `z_i`
+    pub arity: usize,
+    pub nonzero_a: usize,
+    pub nonzero_b: usize,
+    pub nonzero_c: usize,
+    /// number of constraints by nonzero entries in A, B and C together,
+    /// bucketed by the next power of two
+    pub row_density: BTreeMap<usize, usize>,
+}
+
+impl R1CSStats {
+    pub fn nonzero_entries(&self) -> usize {
+        self.nonzero_a + self.nonzero_b + self.nonzero_c
+    }
+}
+
+impl<Fr: PrimeField> R1CS<Fr> {
+    /// Count constraints, variables and nonzero matrix entries of the circuit.
+    pub fn stats(&self) -> R1CSStats {
+        let mut row_density = BTreeMap::new();
+        let (mut nonzero_a, mut nonzero_b, mut nonzero_c) = (0, 0, 0);
+        for (a, b, c) in &self.constraints {
+            nonzero_a += a.len();
+            nonzero_b += b.len();
+            nonzero_c += c.len();
+            let row = (a.len() + b.len() + c.len()).next_power_of_two();
+            *row_density.entry(row).or_insert(0) += 1;
+        }
+        R1CSStats {
+            num_constraints: self.constraints.len(),
+            num_variables: self.num_variables,
+            num_public: self.num_inputs - 1,
+            arity: (self.num_inputs - 1) / 2,
+            nonzero_a,
+            nonzero_b,
+            nonzero_c,
+            row_density,
+        }
+    }
+}
+
+/// Size of a circuit once Nova folds it, to size circuits before proving.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CircuitReport {
+    pub step: R1CSStats,
+    pub primary_constraints: usize,
+    pub primary_variables: usize,
+    pub secondary_constraints: usize,
+    pub secondary_variables: usize,
+    /// constraints Nova's augmented circuit adds to every primary step
+    pub augmented_constraints: usize,
+    /// variables Nova's augmented circuit adds to every primary step
+    pub augmented_variables: usize,
+    /// order-of-magnitude estimate of the prover's peak memory, in bytes
+    pub estimated_prover_memory: usize,
+}
+
+/// Rough prover memory for one circuit: commitment key generators, the R1CS
+/// matrices, and witness and error vectors for the running and new instances.
+fn estimate_memory(constraints: usize, variables: usize, nonzero_entries: usize) -> usize {
+    constraints.max(variables) * POINT_BYTES
+        + nonzero_entries * ENTRY_BYTES
+        + 2 * (constraints + variables) * FIELD_BYTES
+}
+
+impl CircuitReport {
+    pub fn new(r1cs: &R1CS<crate::F1>, pp: &PublicParams<G1, G2, C1, C2>) -> Self {
+        let step = r1cs.stats();
+        let (primary_constraints, secondary_constraints) = pp.num_constraints();
+        let (primary_variables, secondary_variables) = pp.num_variables();
+        let augmented_constraints = primary_constraints.saturating_sub(step.num_constraints);
+        let augmented_variables = primary_variables.saturating_sub(step.num_variables);
+
+        // the augmented and secondary circuits' matrices are not exposed, so
+        // assume three entries per constraint for them
+        let estimated_prover_memory = estimate_memory(
+            primary_constraints,
+            primary_variables,
+            step.nonzero_entries() + 3 * augmented_constraints,
+        ) + estimate_memory(
+            secondary_constraints,
+            secondary_variables,
+            3 * secondary_constraints,
+        );
+
+        CircuitReport {
+            step,
+            primary_constraints,
+            primary_variables,
+            secondary_constraints,
+            secondary_variables,
+            augmented_constraints,
+            augmented_variables,
+            estimated_prover_memory,
+        }
+    }
+}
+
+impl fmt::Display for CircuitReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Number of constraints per step (primary circuit): {}",
+            self.primary_constraints
+        )?;
+        writeln!(
+            f,
+            "Number of constraints per step (secondary circuit): {}",
+            self.secondary_constraints
+        )?;
+        writeln!(
+            f,
+            "Number of variables per step (primary circuit): {}",
+            self.primary_variables
+        )?;
+        writeln!(
+            f,
+            "Number of variables per step (secondary circuit): {}",
+            self.secondary_variables
+        )?;
+        writeln!(
+            f,
+            "Step circuit: {} constraints, {} variables, {} public signals, arity {}",
+            self.step.num_constraints,
+            self.step.num_variables,
+            self.step.num_public,
+            self.step.arity
+        )?;
+        writeln!(
+            f,
+            "Nonzero entries: A {}, B {}, C {}",
+            self.step.nonzero_a, self.step.nonzero_b, self.step.nonzero_c
+        )?;
+        let histogram = self
+            .step
+            .row_density
+            .iter()
+            .map(|(entries, rows)| format!("<={}: {}", entries, rows))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(f, "Constraints by nonzero entries: {}", histogram)?;
+        writeln!(
+            f,
+            "Nova augmented circuit overhead: {} constraints, {} variables",
+            self.augmented_constraints, self.augmented_variables
+        )?;
+        write!(
+            f,
+            "Estimated prover memory: {:.1} MiB",
+            self.estimated_prover_memory as f64 / (1024.0 * 1024.0)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::F1;
+
+    #[test]
+    fn test_r1cs_stats() {
+        let one = F1::from(1);
+        let r1cs = R1CS {
+            num_inputs: 5,
+            num_aux: 1,
+            num_variables: 6,
+            constraints: vec![
+                (vec![(3, one)], vec![(4, one)], vec![(5, one)]),
+                (
+                    vec![(3, one), (5, one)],
+                    vec![(0, one)],
+                    vec![(1, one), (2, one), (4, one)],
+                ),
+            ],
+            witness_indices: None,
+        };
+        let stats = r1cs.stats();
+        assert_eq!(stats.num_constraints, 2);
+        assert_eq!(stats.num_public, 4);
+        assert_eq!(stats.arity, 2);
+        assert_eq!(
+            (stats.nonzero_a, stats.nonzero_b, stats.nonzero_c),
+            (3, 2, 4)
+        );
+        assert_eq!(stats.nonzero_entries(), 9);
+        assert_eq!(stats.row_density, BTreeMap::from([(4, 1), (8, 1)]));
+    }
+}
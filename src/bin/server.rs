@@ -0,0 +1,73 @@
+//! Local proving service, see `nova_scotia::server`.
+//!
+//! usage: server [--addr 127.0.0.1:8080] [--workers 2] [--queue 64] --circuit <id>=<r1cs>,<witness generator>[,<sym>] ...
+//!
+//! With the circuit's `.sym` file, jobs with bad private inputs are rejected
+//! when they are submitted.
+use std::{
+    collections::HashMap,
+    net::TcpListener,
+    path::{Path, PathBuf},
+};
+
+use nova_scotia::{
+    circom::{inputs::InputSignals, reader::load_r1cs},
+    server::{ProvingService, RegisteredCircuit},
+    FileLocation,
+};
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: server [--addr 127.0.0.1:8080] [--workers 2] [--queue 64] --circuit <id>=<r1cs>,<witness generator>[,<sym>] ..."
+    );
+    std::process::exit(2)
+}
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let mut addr = "127.0.0.1:8080".to_string();
+    let mut workers = 2;
+    let mut queue_capacity = 64;
+    let mut circuits = HashMap::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--addr" => addr = value,
+            "--workers" => workers = value.parse().unwrap_or_else(|_| usage()),
+            "--queue" => queue_capacity = value.parse().unwrap_or_else(|_| usage()),
+            "--circuit" => {
+                let (id, files) = value.split_once('=').unwrap_or_else(|| usage());
+                let files: Vec<&str> = files.split(',').collect();
+                let (r1cs_path, witness_generator, sym) = match files[..] {
+                    [r1cs, witness_generator] => (r1cs, witness_generator, None),
+                    [r1cs, witness_generator, sym] => (r1cs, witness_generator, Some(sym)),
+                    _ => usage(),
+                };
+                println!("Loading circuit {} and creating its public params...", id);
+                let r1cs = load_r1cs(&FileLocation::PathBuf(PathBuf::from(r1cs_path)));
+                let mut circuit = RegisteredCircuit::new(r1cs, PathBuf::from(witness_generator));
+                if let Some(sym) = sym {
+                    circuit = circuit.with_inputs(InputSignals::from_files(
+                        Path::new(r1cs_path),
+                        Path::new(sym),
+                    )?);
+                }
+                circuits.insert(id.to_string(), circuit);
+            }
+            _ => usage(),
+        }
+    }
+    if circuits.is_empty() {
+        usage();
+    }
+
+    let listener = TcpListener::bind(&addr)?;
+    println!("Listening on {}", listener.local_addr()?);
+    ProvingService::new(circuits, workers, queue_capacity)
+        .serve(listener)
+        .await
+}
@@ -0,0 +1,74 @@
+//! Verify a compressed proof from the command line, see `nova_scotia::verify`.
+//!
+//! usage: verify --params <params.json> --proof <proof.json> --steps <n> --z0 <x,...> [--expected <x,...>]
+//!
+//! `z0` and `expected` are comma-separated decimal field elements. Prints the
+//! final step output `z_n` as a JSON array of decimal strings, or exits with
+//! status 1 if the proof does not verify.
+use std::{fs, path::PathBuf};
+
+use ff::PrimeField;
+use nova_scotia::{
+    public_params_from_bytes,
+    verify::{to_decimal_strings, verify_compressed_proof_with_expected},
+    F1,
+};
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: verify --params <params.json> --proof <proof.json> --steps <n> --z0 <x,...> [--expected <x,...>]"
+    );
+    std::process::exit(2)
+}
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1)
+}
+
+fn parse_field_elements(value: &str) -> Vec<F1> {
+    value
+        .split(',')
+        .map(|x| F1::from_str_vartime(x.trim()).unwrap_or_else(|| usage()))
+        .collect()
+}
+
+fn main() {
+    let mut params = None;
+    let mut proof = None;
+    let mut steps = None;
+    let mut z0 = None;
+    let mut expected = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--params" => params = Some(PathBuf::from(value)),
+            "--proof" => proof = Some(PathBuf::from(value)),
+            "--steps" => steps = Some(value.parse::<usize>().unwrap_or_else(|_| usage())),
+            "--z0" => z0 = Some(parse_field_elements(&value)),
+            "--expected" => expected = Some(parse_field_elements(&value)),
+            _ => usage(),
+        }
+    }
+    let (Some(params), Some(proof), Some(steps), Some(z0)) = (params, proof, steps, z0) else {
+        usage()
+    };
+
+    let read = |path: &PathBuf| {
+        fs::read(path).unwrap_or_else(|e| fail(format!("{}: {}", path.display(), e)))
+    };
+    let pp = public_params_from_bytes(&read(&params))
+        .unwrap_or_else(|e| fail(format!("invalid verifier key: {}", e)));
+    let proof = serde_json::from_slice(&read(&proof))
+        .unwrap_or_else(|e| fail(format!("invalid proof: {}", e)));
+
+    match verify_compressed_proof_with_expected(&pp, &proof, steps, z0, expected.as_deref()) {
+        Ok(z_n) => println!(
+            "{}",
+            serde_json::to_string(&to_decimal_strings(&z_n)).unwrap()
+        ),
+        Err(e) => fail(e),
+    }
+}
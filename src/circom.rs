@@ -1,5 +1,20 @@
 pub mod circuit;
+#[cfg(all(feature = "prover", not(target_family = "wasm")))]
+pub mod compile;
+#[cfg(all(feature = "prover", not(target_family = "wasm")))]
+pub mod cross_check;
+#[cfg(feature = "prover")]
 pub mod file;
+#[cfg(feature = "prover")]
+pub mod inputs;
+#[cfg(all(feature = "ffi", not(target_family = "wasm")))]
+pub mod library;
+pub mod optimize;
+#[cfg(feature = "prover")]
 pub mod reader;
-#[cfg(target_family = "wasm")]
+#[cfg(feature = "prover")]
+pub mod sym;
+#[cfg(all(feature = "prover", target_family = "wasm"))]
 pub mod wasm;
+#[cfg(all(feature = "prover", target_family = "wasm"))]
+pub mod witness_calculator;
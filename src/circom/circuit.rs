@@ -26,6 +26,72 @@ pub struct R1CS<Fr: PrimeField> {
     pub num_aux: usize,
     pub num_variables: usize,
     pub constraints: Vec<Constraint<Fr>>,
+    /// For every wire, its index in the witness produced by circom. `None` when
+    /// wires and witness line up one to one, i.e. the R1CS was not optimized.
+    pub witness_indices: Option<Vec<usize>>,
+}
+
+impl<Fr: PrimeField> R1CS<Fr> {
+    /// Reorder a witness produced by circom to match the wires of this R1CS.
+    pub fn map_witness(&self, witness: Vec<Fr>) -> Vec<Fr> {
+        match &self.witness_indices {
+            None => witness,
+            Some(indices) => indices.iter().map(|&i| witness[i]).collect(),
+        }
+    }
+
+    /// Reorder the public outputs so that element `k` of `z_{i+1}` is the
+    /// circuit's output `order[k]`, for circuits whose outputs are not declared
+    /// in the same order as their step input.
+    pub fn with_output_order(&self, order: &[usize]) -> std::io::Result<R1CS<Fr>> {
+        let arity = (self.num_inputs - 1) / 2;
+        let mut sorted = order.to_vec();
+        sorted.sort_unstable();
+        if sorted != (0..arity).collect::<Vec<_>>() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "output order {:?} is not a permutation of 0..{}",
+                    order, arity
+                ),
+            ));
+        }
+
+        // wire 0 is the constant one, followed by the outputs at 1..=arity
+        let mut new_wire: Vec<usize> = (0..self.num_variables).collect();
+        let mut old_wire: Vec<usize> = (0..self.num_variables).collect();
+        for (k, &output) in order.iter().enumerate() {
+            new_wire[1 + output] = 1 + k;
+            old_wire[1 + k] = 1 + output;
+        }
+
+        let remap = |lc: &Vec<(usize, Fr)>| {
+            lc.iter()
+                .map(|&(index, coeff)| (new_wire[index], coeff))
+                .collect::<Vec<_>>()
+        };
+        let constraints = self
+            .constraints
+            .iter()
+            .map(|(a, b, c)| (remap(a), remap(b), remap(c)))
+            .collect();
+
+        let witness_indices = old_wire
+            .iter()
+            .map(|&wire| match &self.witness_indices {
+                None => wire,
+                Some(indices) => indices[wire],
+            })
+            .collect();
+
+        Ok(R1CS {
+            num_inputs: self.num_inputs,
+            num_aux: self.num_aux,
+            num_variables: self.num_variables,
+            constraints,
+            witness_indices: Some(witness_indices),
+        })
+    }
 }
 
 #[derive(Clone)]
@@ -0,0 +1,316 @@
+//! Run a locally installed `circom` and build the C++ witness generator.
+//!
+//! Usable at runtime or from a `build.rs`:
+//!
+//! ```no_run
+//! use nova_scotia::circom::compile::{compile_circuit, CompileOptions};
+//!
+//! let compiled = compile_circuit("circuits/main.circom", &CompileOptions::default()).unwrap();
+//! for source in &compiled.sources {
+//!     println!("cargo:rerun-if-changed={}", source.display());
+//! }
+//! ```
+//!
+//! A stamp file next to the outputs records a digest of every `.circom` source
+//! and of the options, so unchanged circuits are not compiled again.
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use sha2::{Digest as _, Sha256};
+
+use crate::digest::Digest;
+
+/// circom `--prime` matching the scalar field of the primary curve, [`crate::F1`]
+pub const CIRCOM_PRIME: &str = "vesta";
+
+#[derive(Clone, Debug)]
+pub struct CompileOptions {
+    /// `circom` executable
+    pub circom: PathBuf,
+    /// defaults to the directory of the main `.circom` file
+    pub output_dir: Option<PathBuf>,
+    /// library directories, circom's `-l`
+    pub include_paths: Vec<PathBuf>,
+    /// generate the wasm witness generator
+    pub wasm: bool,
+    /// generate and build (with `make`) the C++ witness generator
+    pub cpp: bool,
+    /// also build the C++ witness generator as a shared library, see
+    /// [`build_witness_library`]
+    pub cpp_library: bool,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        CompileOptions {
+            circom: PathBuf::from("circom"),
+            output_dir: None,
+            include_paths: vec![],
+            wasm: true,
+            cpp: true,
+            cpp_library: false,
+        }
+    }
+}
+
+/// Paths of the artifacts of a compiled circuit.
+#[derive(Clone, Debug)]
+pub struct CompiledCircuit {
+    pub r1cs: PathBuf,
+    pub sym: PathBuf,
+    pub wasm: Option<PathBuf>,
+    pub cpp: Option<PathBuf>,
+    pub cpp_library: Option<PathBuf>,
+    /// the main `.circom` file and every file it includes
+    pub sources: Vec<PathBuf>,
+    /// false if the artifacts were up to date
+    pub recompiled: bool,
+}
+
+/// find `include "...";` directives, ignoring comments
+fn includes(source: &str) -> Vec<String> {
+    let mut includes = vec![];
+    let mut in_block_comment = false;
+    for line in source.lines() {
+        let mut line = line.trim();
+        if in_block_comment {
+            match line.find("*/") {
+                Some(end) => {
+                    in_block_comment = false;
+                    line = line[end + 2..].trim();
+                }
+                None => continue,
+            }
+        }
+        if line.starts_with("/*") && !line.contains("*/") {
+            in_block_comment = true;
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("include") {
+            if let Some(name) = rest
+                .trim()
+                .strip_prefix('"')
+                .and_then(|r| r.split('"').next())
+            {
+                includes.push(name.to_string());
+            }
+        }
+    }
+    includes
+}
+
+/// the main source and all the sources it includes, recursively
+fn collect_sources(main: &Path, include_paths: &[PathBuf]) -> std::io::Result<Vec<PathBuf>> {
+    let mut seen = BTreeSet::new();
+    let mut stack = vec![main.to_path_buf()];
+    while let Some(path) = stack.pop() {
+        let path = path.canonicalize()?;
+        if !seen.insert(path.clone()) {
+            continue;
+        }
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        for include in includes(&fs::read_to_string(&path)?) {
+            let candidate = std::iter::once(dir.to_path_buf())
+                .chain(include_paths.iter().cloned())
+                .map(|dir| dir.join(&include))
+                .find(|candidate| candidate.exists());
+            match candidate {
+                Some(candidate) => stack.push(candidate),
+                // circom will report it, the digest then only covers its name
+                None => tracing::debug!("could not resolve include {}", include),
+            }
+        }
+    }
+    Ok(seen.into_iter().collect())
+}
+
+fn sources_digest(sources: &[PathBuf], options: &CompileOptions) -> std::io::Result<Digest> {
+    let mut hasher = Sha256::new();
+    hasher.update(format!(
+        "{} wasm={} cpp={} cpp_library={} {:?}\n",
+        CIRCOM_PRIME, options.wasm, options.cpp, options.cpp_library, options.include_paths
+    ));
+    for source in sources {
+        hasher.update(source.to_string_lossy().as_bytes());
+        hasher.update(fs::read(source)?);
+    }
+    Ok(Digest(hasher.finalize().into()))
+}
+
+fn run(command: &mut Command) -> std::io::Result<()> {
+    let output = command.output().map_err(|e| {
+        Error::new(
+            e.kind(),
+            format!("failed to run {:?}: {}", command.get_program(), e),
+        )
+    })?;
+    if !output.status.success() {
+        return Err(Error::other(format!(
+            "{:?} failed with {}: {} {}",
+            command.get_program(),
+            output.status,
+            String::from_utf8_lossy(&output.stdout).trim(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+fn witness_library_path(cpp_dir: &Path, name: &str) -> PathBuf {
+    cpp_dir.join(format!("{}.{}", name, std::env::consts::DLL_EXTENSION))
+}
+
+/// Build the C++ witness generator that circom wrote to `cpp_dir` (its `--c`
+/// output) as a shared library, `<cpp_dir>/<name>.so` or the platform's
+/// equivalent, next to the circuit's `<name>.dat`.
+///
+/// Needs a C++ compiler (`$CXX`, by default `c++`), `nasm` and GMP, like
+/// circom's Makefile. The library is loaded with
+/// [`WitnessLibrary`](crate::circom::library::WitnessLibrary) when the `ffi`
+/// feature is enabled.
+pub fn build_witness_library(cpp_dir: &Path, name: &str) -> std::io::Result<PathBuf> {
+    let (nasm_format, symbol_prefix) = if cfg!(target_os = "macos") {
+        ("-fmacho64", Some("_"))
+    } else {
+        ("-felf64", None)
+    };
+    let mut nasm = Command::new("nasm");
+    nasm.current_dir(cpp_dir)
+        .args([nasm_format, "fr.asm", "-o", "fr_asm.o"]);
+    if let Some(prefix) = symbol_prefix {
+        nasm.args(["--prefix", prefix]);
+    }
+    run(&mut nasm)?;
+
+    let library = witness_library_path(cpp_dir, name);
+    let shim = Path::new(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/circom/cpp_deps/witness_library.cpp"
+    ));
+    let cxx = std::env::var_os("CXX").unwrap_or_else(|| "c++".into());
+    run(Command::new(cxx)
+        .current_dir(cpp_dir)
+        .args(["-std=c++11", "-O3", "-fPIC", "-shared", "-I."])
+        // keep circom's loadCircuit, but not its main
+        .arg("-Dmain=nova_scotia_circom_main")
+        .args(["main.cpp", "calcwit.cpp", "fr.cpp", "fr_asm.o"])
+        .arg(format!("{}.cpp", name))
+        .arg(shim)
+        .args(["-lgmp", "-o"])
+        .arg(&library))?;
+    Ok(library)
+}
+
+/// Compile `source` with circom for [`CIRCOM_PRIME`] and build its C++ witness
+/// generator, unless the sources and options are unchanged since the last run.
+#[tracing::instrument(skip_all, fields(source = %source.as_ref().display()))]
+pub fn compile_circuit(
+    source: impl AsRef<Path>,
+    options: &CompileOptions,
+) -> std::io::Result<CompiledCircuit> {
+    let source = source.as_ref();
+    let name = source
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "invalid circom source path"))?;
+    let output_dir = match &options.output_dir {
+        Some(dir) => dir.clone(),
+        None => source
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .to_path_buf(),
+    };
+
+    let sources = collect_sources(source, &options.include_paths)?;
+    let digest = sources_digest(&sources, options)?.to_string();
+
+    let cpp_dir = output_dir.join(format!("{}_cpp", name));
+    let compiled = CompiledCircuit {
+        r1cs: output_dir.join(format!("{}.r1cs", name)),
+        sym: output_dir.join(format!("{}.sym", name)),
+        wasm: options.wasm.then(|| {
+            output_dir
+                .join(format!("{}_js", name))
+                .join(format!("{}.wasm", name))
+        }),
+        cpp: options.cpp.then(|| cpp_dir.join(name)),
+        cpp_library: options
+            .cpp_library
+            .then(|| witness_library_path(&cpp_dir, name)),
+        sources,
+        recompiled: true,
+    };
+
+    let stamp = output_dir.join(format!("{}.circom.sha256", name));
+    let artifacts_exist = compiled.r1cs.exists()
+        && compiled.sym.exists()
+        && compiled
+            .wasm
+            .iter()
+            .chain(&compiled.cpp)
+            .chain(&compiled.cpp_library)
+            .all(|p| p.exists());
+    if artifacts_exist && fs::read_to_string(&stamp).ok().as_deref() == Some(digest.as_str()) {
+        tracing::debug!("artifacts are up to date");
+        return Ok(CompiledCircuit {
+            recompiled: false,
+            ..compiled
+        });
+    }
+
+    fs::create_dir_all(&output_dir)?;
+    let mut circom = Command::new(&options.circom);
+    circom
+        .arg(source)
+        .args(["--r1cs", "--sym", "--prime", CIRCOM_PRIME, "--output"])
+        .arg(&output_dir);
+    if options.wasm {
+        circom.arg("--wasm");
+    }
+    if options.cpp || options.cpp_library {
+        circom.arg("--c");
+    }
+    for path in &options.include_paths {
+        circom.arg("-l").arg(path);
+    }
+    run(&mut circom)?;
+    if options.cpp {
+        run(Command::new("make").current_dir(&cpp_dir))?;
+    }
+    if options.cpp_library {
+        build_witness_library(&cpp_dir, name)?;
+    }
+
+    fs::write(stamp, digest)?;
+    Ok(compiled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_includes() {
+        let source = r#"
+            pragma circom 2.0.3;
+            include "../node_modules/circomlib/circuits/sha256/sha256.circom";
+            // include "commented.circom";
+            /*
+            include "also_commented.circom";
+            */
+            include "bitify.circom";
+        "#;
+        assert_eq!(
+            includes(source),
+            vec![
+                "../node_modules/circomlib/circuits/sha256/sha256.circom",
+                "bitify.circom"
+            ]
+        );
+    }
+}
@@ -0,0 +1,103 @@
+// C interface to circom's C++ witness generator, for loading it as a shared
+// library. Built together with the generated sources, with circom's main.cpp
+// compiled with -Dmain=nova_scotia_circom_main so that its loadCircuit can be
+// reused, see `circom::compile::build_witness_library`.
+#include <cstdint>
+#include <cstdio>
+#include <cstring>
+#include <memory>
+#include <stdexcept>
+#include <string>
+
+#include "calcwit.hpp"
+#include "circom.hpp"
+
+// defined in main.cpp and in the circuit's generated .cpp file
+Circom_Circuit* loadCircuit(std::string const &datFileName);
+u64 fnv1a(std::string s);
+uint get_size_of_witness();
+
+static void set_error(char* error, size_t error_len, std::string const &message) {
+  if (error_len > 0) {
+    snprintf(error, error_len, "%s", message.c_str());
+  }
+}
+
+// circom's own lookup asserts when a signal is missing, aborting the process
+static bool has_input_signal(Circom_Circuit* circuit, u64 h) {
+  uint n = get_size_of_input_hashmap();
+  for (uint k = 0; k < n; k++) {
+    const HashSignalInfo &info = circuit->InputHashMap[(h + k) % n];
+    if (info.hash == h) return true;
+    if (info.hash == 0) return false;
+  }
+  return false;
+}
+
+extern "C" {
+
+uint32_t nova_scotia_field_bytes() { return Fr_N64 * 8; }
+
+void* nova_scotia_circuit_load(const char* dat_path, char* error, size_t error_len) {
+  try {
+    return loadCircuit(dat_path);
+  } catch (std::exception &e) {
+    set_error(error, error_len, e.what());
+    return nullptr;
+  }
+}
+
+void nova_scotia_circuit_free(void* circuit) {
+  delete static_cast<Circom_Circuit*>(circuit);
+}
+
+uint32_t nova_scotia_witness_size(const void* circuit) { return get_size_of_witness(); }
+
+// `values` holds the elements of every signal, in order, as little-endian
+// integers of nova_scotia_field_bytes() bytes. The witness is written to
+// `witness` in the same encoding.
+int nova_scotia_witness_calculate(void* circuit, uint32_t n_signals,
+                                  const char* const* names, const uint32_t* sizes,
+                                  const uint8_t* values, uint8_t* witness, char* error,
+                                  size_t error_len) {
+  try {
+    Circom_Circuit* c = static_cast<Circom_Circuit*>(circuit);
+    std::unique_ptr<Circom_CalcWit> ctx(new Circom_CalcWit(c));
+    const uint8_t* value = values;
+    for (uint32_t s = 0; s < n_signals; s++) {
+      u64 h = fnv1a(names[s]);
+      if (!has_input_signal(c, h)) {
+        throw std::runtime_error("signal " + std::string(names[s]) + " not found");
+      }
+      if (ctx->getInputSignalSize(h) != sizes[s]) {
+        throw std::runtime_error("input signal " + std::string(names[s]) + " has " +
+                                 std::to_string(ctx->getInputSignalSize(h)) +
+                                 " elements, not " + std::to_string(sizes[s]));
+      }
+      for (uint32_t i = 0; i < sizes[s]; i++) {
+        FrElement v;
+        v.shortVal = 0;
+        v.type = Fr_LONG;
+        memcpy(v.longVal, value, Fr_N64 * 8);
+        value += Fr_N64 * 8;
+        // the witness is computed once the last input is set
+        ctx->setInputSignal(h, i, v);
+      }
+    }
+    if (ctx->getRemaingInputsToBeSet() != 0) {
+      throw std::runtime_error("not all input signals were set");
+    }
+
+    for (uint i = 0; i < get_size_of_witness(); i++) {
+      FrElement v;
+      ctx->getWitness(i, &v);
+      Fr_toLongNormal(&v, &v);
+      memcpy(witness + i * Fr_N64 * 8, v.longVal, Fr_N64 * 8);
+    }
+    return 0;
+  } catch (std::exception &e) {
+    set_error(error, error_len, e.what());
+    return 1;
+  }
+}
+}
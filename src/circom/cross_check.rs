@@ -0,0 +1,327 @@
+//! Differential testing of witness generators: every generator available for a
+//! circuit computes the witness of the same input, and the witnesses are
+//! compared element by element.
+//!
+//! The browser build runs the same `.wasm` as node, through `WitnessCalculator`,
+//! so it is covered by the [`WitnessGeneratorKind::Wasm`] generator here.
+use std::{collections::HashMap, fmt, io::Error, path::Path, process::Command};
+
+use serde_json::Value;
+
+use crate::{
+    circom::{
+        reader::{generate_witness_from_bin, generate_witness_from_wasm},
+        sym::SymbolTable,
+    },
+    manifest::{WitnessGeneratorKind, WitnessGeneratorManifest},
+    to_decimal_string, FileLocation, F1,
+};
+
+/// The first witness element on which the generators disagree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WitnessMismatch {
+    /// index in the witness
+    pub index: usize,
+    /// name of the signal at `index`, if a `.sym` file was given
+    pub signal: Option<String>,
+    /// the element computed by each generator, `None` past the end of its witness
+    pub values: Vec<(WitnessGeneratorKind, Option<F1>)>,
+}
+
+impl fmt::Display for WitnessMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "witness element {}", self.index)?;
+        if let Some(signal) = &self.signal {
+            write!(f, " ({})", signal)?;
+        }
+        write!(f, " differs:")?;
+        for (kind, value) in &self.values {
+            match value {
+                Some(value) => write!(f, " {:?}={}", kind, to_decimal_string(value))?,
+                None => write!(f, " {:?}=missing", kind)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Result of running every generator on one input.
+#[derive(Clone, Debug)]
+pub struct CrossCheck {
+    /// generators that were run
+    pub checked: Vec<WitnessGeneratorKind>,
+    /// generators that were not available, with the reason
+    pub skipped: Vec<(WitnessGeneratorKind, String)>,
+    pub mismatch: Option<WitnessMismatch>,
+}
+
+/// Index of the first element on which `witnesses` differ, including a witness
+/// ending before the others.
+pub fn first_mismatch<Fr: PartialEq>(witnesses: &[Vec<Fr>]) -> Option<usize> {
+    let len = witnesses.iter().map(Vec::len).max().unwrap_or(0);
+    (0..len).find(|&i| {
+        let first = witnesses[0].get(i);
+        witnesses[1..].iter().any(|w| w.get(i) != first)
+    })
+}
+
+/// why `generator` can't be run here, if it can't
+fn unavailable(generator: &WitnessGeneratorManifest) -> Option<String> {
+    if !generator.path.exists() {
+        return Some(format!("{} not found", generator.path.display()));
+    }
+    match generator.kind {
+        WitnessGeneratorKind::Wasm => match Command::new("node").arg("--version").output() {
+            Ok(output) if output.status.success() => None,
+            _ => Some("node not found".to_string()),
+        },
+        // e.g. a binary built for another platform, only usage is printed
+        // when it does run
+        WitnessGeneratorKind::Cpp => match Command::new(&generator.path).output() {
+            Ok(_) => None,
+            Err(e) => Some(format!("{} can't be run: {}", generator.path.display(), e)),
+        },
+        WitnessGeneratorKind::Library if cfg!(feature = "ffi") => None,
+        WitnessGeneratorKind::Library => Some("needs the ffi feature".to_string()),
+    }
+}
+
+fn generate(
+    generator: &WitnessGeneratorManifest,
+    input: &HashMap<String, Value>,
+    witness_output: &Path,
+) -> std::io::Result<Vec<F1>> {
+    let input_json = serde_json::to_string(input)?;
+    match generator.kind {
+        WitnessGeneratorKind::Cpp => {
+            generate_witness_from_bin::<F1>(&generator.path, &input_json, witness_output)
+        }
+        WitnessGeneratorKind::Wasm => generate_witness_from_wasm::<F1>(
+            &FileLocation::PathBuf(generator.path.clone()),
+            &input_json,
+            witness_output,
+        ),
+        #[cfg(feature = "ffi")]
+        WitnessGeneratorKind::Library => {
+            crate::circom::library::WitnessLibrary::open(&generator.path)?
+                .calculate(&crate::step_input::input_signals(input)?)
+        }
+        #[cfg(not(feature = "ffi"))]
+        WitnessGeneratorKind::Library => unreachable!(),
+    }
+}
+
+/// Compute the witness of `input`, the full circom input json, with every
+/// available generator of the same circuit and compare them. Witness files are
+/// written to `work_dir` and removed afterwards.
+///
+/// A generator failing on an input the others accept is reported as an error.
+/// Nothing is compared unless at least two generators are in `checked`.
+pub fn cross_check_witness(
+    generators: &[WitnessGeneratorManifest],
+    input: &HashMap<String, Value>,
+    sym: Option<&SymbolTable>,
+    work_dir: &Path,
+) -> std::io::Result<CrossCheck> {
+    let mut checked = vec![];
+    let mut skipped = vec![];
+    let mut witnesses = vec![];
+    for (i, generator) in generators.iter().enumerate() {
+        if let Some(reason) = unavailable(generator) {
+            skipped.push((generator.kind, reason));
+            continue;
+        }
+        let witness_output = work_dir.join(format!("cross_check_{}.wtns", i));
+        let witness = generate(generator, input, &witness_output);
+        let _ = std::fs::remove_file(&witness_output);
+        let witness = witness.map_err(|e| {
+            Error::new(
+                e.kind(),
+                format!("{:?} witness generator: {}", generator.kind, e),
+            )
+        })?;
+        checked.push(generator.kind);
+        witnesses.push(witness);
+    }
+    let mismatch = first_mismatch(&witnesses).map(|index| WitnessMismatch {
+        index,
+        signal: sym.and_then(|sym| {
+            sym.symbols
+                .iter()
+                .find(|symbol| symbol.witness == Some(index))
+                .map(|symbol| symbol.name.clone())
+        }),
+        values: checked
+            .iter()
+            .zip(&witnesses)
+            .map(|(kind, witness)| (*kind, witness.get(index).copied()))
+            .collect(),
+    });
+    Ok(CrossCheck {
+        checked,
+        skipped,
+        mismatch,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circom::compile::build_witness_library;
+    use serde::Deserialize;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_first_mismatch() {
+        assert_eq!(first_mismatch(&[vec![1, 2, 3], vec![1, 2, 3]]), None);
+        assert_eq!(first_mismatch(&[vec![1, 2, 3], vec![1, 5, 3]]), Some(1));
+        assert_eq!(first_mismatch(&[vec![1, 2], vec![1, 2, 3]]), Some(2));
+        assert_eq!(
+            first_mismatch(&[vec![1, 2], vec![1, 2], vec![0, 2]]),
+            Some(0)
+        );
+    }
+
+    /// Whether the C++ generators circom writes with `--c` can be built here:
+    /// `nasm`, a C++ compiler (`$CXX`, by default `c++`) and the GMP and
+    /// nlohmann/json headers.
+    fn cpp_toolchain(dir: &Path) -> bool {
+        let succeeds =
+            |command: &mut Command| command.output().is_ok_and(|output| output.status.success());
+        let probe = dir.join("probe.cpp");
+        std::fs::write(&probe, "#include <gmp.h>\n#include <nlohmann/json.hpp>\n").unwrap();
+        let cxx = std::env::var_os("CXX").unwrap_or_else(|| "c++".into());
+        succeeds(Command::new("nasm").arg("-v"))
+            && succeeds(
+                Command::new(cxx)
+                    .args(["-std=c++11", "-fsyntax-only"])
+                    .arg(&probe),
+            )
+    }
+
+    /// Build the witness generator binary and library of circom's C++ output in
+    /// `cpp_dir` from a copy in `build_dir`, rather than relying on the
+    /// committed binaries, which are only built for one platform.
+    fn build_cpp_generators(
+        cpp_dir: &Path,
+        name: &str,
+        build_dir: &Path,
+    ) -> [WitnessGeneratorManifest; 2] {
+        for entry in std::fs::read_dir(cpp_dir).unwrap() {
+            let path = entry.unwrap().path();
+            let extension = path.extension().and_then(|extension| extension.to_str());
+            if matches!(extension, Some("cpp" | "hpp" | "asm" | "dat")) {
+                std::fs::copy(&path, build_dir.join(path.file_name().unwrap())).unwrap();
+            }
+        }
+        let library = build_witness_library(build_dir, name).unwrap();
+        let binary = build_dir.join(name);
+        let cxx = std::env::var_os("CXX").unwrap_or_else(|| "c++".into());
+        let status = Command::new(cxx)
+            .current_dir(build_dir)
+            .args(["-std=c++11", "-O3", "-I."])
+            .args(["main.cpp", "calcwit.cpp", "fr.cpp", "fr_asm.o"])
+            .arg(format!("{}.cpp", name))
+            .args(["-lgmp", "-o"])
+            .arg(&binary)
+            .status()
+            .unwrap();
+        assert!(status.success(), "building {} failed", binary.display());
+        [
+            WitnessGeneratorManifest {
+                kind: WitnessGeneratorKind::Cpp,
+                path: binary,
+            },
+            WitnessGeneratorManifest {
+                kind: WitnessGeneratorKind::Library,
+                path: library,
+            },
+        ]
+    }
+
+    /// Cross-check the generators of the circuit `name` in `root`: the wasm one
+    /// if any, and the C++ binary and library, built here when the toolchain
+    /// is present.
+    fn cross_check_example(root: &Path, name: &str, wasm: bool, input: HashMap<String, Value>) {
+        let work_dir = tempfile::tempdir().unwrap();
+        let cpp_dir = root.join(format!("{}_cpp", name));
+        let built = cpp_toolchain(work_dir.path());
+        let mut generators = if built {
+            build_cpp_generators(&cpp_dir, name, work_dir.path()).to_vec()
+        } else {
+            vec![
+                WitnessGeneratorManifest {
+                    kind: WitnessGeneratorKind::Cpp,
+                    path: cpp_dir.join(name),
+                },
+                WitnessGeneratorManifest {
+                    kind: WitnessGeneratorKind::Library,
+                    path: cpp_dir.join(format!("{}.{}", name, std::env::consts::DLL_EXTENSION)),
+                },
+            ]
+        };
+        if wasm {
+            generators.push(WitnessGeneratorManifest {
+                kind: WitnessGeneratorKind::Wasm,
+                path: root.join(format!("{}_js/{}.wasm", name, name)),
+            });
+        }
+        let sym = SymbolTable::from_file(root.join(format!("{}.sym", name))).unwrap();
+
+        let runnable = generators
+            .iter()
+            .filter(|generator| unavailable(generator).is_none())
+            .count();
+        let result = cross_check_witness(&generators, &input, Some(&sym), work_dir.path()).unwrap();
+        assert_eq!(result.checked.len(), runnable, "{:?}", result.skipped);
+        if built {
+            assert!(result.checked.contains(&WitnessGeneratorKind::Cpp));
+            if cfg!(feature = "ffi") {
+                assert!(result.checked.len() >= 2, "{:?}", result.skipped);
+            }
+        }
+        if let Some(mismatch) = result.mismatch {
+            panic!("{}: {}", name, mismatch);
+        }
+    }
+
+    #[test]
+    fn test_cross_check_toy() {
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("examples/toy");
+        let input = serde_json::from_value(json!({"step_in": ["1", "1"], "adder": "3"})).unwrap();
+        cross_check_example(&root, "toy", true, input);
+    }
+
+    /// Needs the circuit compiled by `examples/bitcoin/circom/compile.sh`,
+    /// which is not committed; skipped without it.
+    #[test]
+    fn test_cross_check_bitcoin() {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Blocks {
+            prev_block_hash: [String; 2],
+            block_hashes: Vec<[String; 2]>,
+            block_headers: Vec<Vec<u8>>,
+        }
+
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("examples/bitcoin/circom");
+        let Ok(sym) = SymbolTable::from_file(root.join("bitcoin_benchmark.sym")) else {
+            return;
+        };
+        let block_count = (0..)
+            .take_while(|i| sym.get(&format!("main.blockHashes[{}][0]", i)).is_some())
+            .count();
+        let blocks: Blocks = serde_json::from_str(include_str!(
+            "../../examples/bitcoin/fetcher/btc-blocks.json"
+        ))
+        .unwrap();
+        let input = serde_json::from_value(json!({
+            "step_in": blocks.prev_block_hash,
+            "blockHashes": blocks.block_hashes[..block_count],
+            "blockHeaders": blocks.block_headers[..block_count],
+        }))
+        .unwrap();
+        cross_check_example(&root, "bitcoin_benchmark", false, input);
+    }
+}
@@ -4,6 +4,7 @@
 use crate::circom::circuit::Constraint;
 use byteorder::{LittleEndian, ReadBytesExt};
 use ff::PrimeField;
+use num_bigint::BigUint;
 use pasta_curves::group::Group;
 use std::{
     collections::HashMap,
@@ -35,18 +36,77 @@ pub struct R1CSFile<Fr: PrimeField> {
     pub wire_mapping: Vec<u64>,
 }
 
+/// Upper bounds on the sizes declared in untrusted `.r1cs` and `.wtns` files.
+///
+/// Section sizes are also checked against the declared counts and the file
+/// length, so a small file cannot make the parser allocate much more memory
+/// than its own size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// wires of an r1cs, or elements of a witness
+    pub max_wires: u32,
+    pub max_constraints: u32,
+    /// terms of all the linear combinations of an r1cs together
+    pub max_terms: u64,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        ParseLimits {
+            max_wires: 1 << 26,
+            max_constraints: 1 << 26,
+            max_terms: 1 << 28,
+        }
+    }
+}
+
+impl ParseLimits {
+    /// no limits beyond the consistency checks, for trusted files
+    pub fn unlimited() -> Self {
+        ParseLimits {
+            max_wires: u32::MAX,
+            max_constraints: u32::MAX,
+            max_terms: u64::MAX,
+        }
+    }
+}
+
+fn invalid_data(message: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidData, message.into())
+}
+
+/// bytes of a term: a wire index and a field element
+const TERM_SIZE: u64 = 4 + 32;
+
 pub(crate) fn read_field<R: Read, Fr: PrimeField>(mut reader: R) -> Result<Fr> {
     let mut repr = Fr::zero().to_repr();
     for digit in repr.as_mut().iter_mut() {
         // TODO: may need to reverse order?
         *digit = reader.read_u8()?;
     }
-    let fr = Fr::from_repr(repr).unwrap();
-    Ok(fr)
+    Option::from(Fr::from_repr(repr)).ok_or_else(|| invalid_data("Non-canonical field element"))
+}
+
+/// Decimal field element, e.g. a coefficient of a JSON r1cs. Like
+/// [`read_field`], values that are not below the modulus are rejected rather
+/// than reduced.
+pub(crate) fn parse_field<Fr: PrimeField>(decimal: &str) -> Result<Fr> {
+    let invalid = || invalid_data(format!("Non-canonical field element {}", decimal));
+    let x = Fr::from_str_vartime(decimal).ok_or_else(invalid)?;
+    // from_str_vartime reduces, and only accepts decimals without leading zeros,
+    // so x is canonical iff it prints back as the same decimal
+    if BigUint::from_bytes_le(x.to_repr().as_ref()).to_str_radix(10) == decimal {
+        Ok(x)
+    } else {
+        Err(invalid())
+    }
 }
 
-fn read_header<R: Read>(mut reader: R, size: u64) -> Result<Header> {
+fn read_header<R: Read>(mut reader: R, size: u64, limits: &ParseLimits) -> Result<Header> {
     let field_size = reader.read_u32::<LittleEndian>()?;
+    if field_size != 32 {
+        return Err(invalid_data("This parser only supports 32-byte fields"));
+    }
     let mut prime_size = vec![0u8; field_size as usize];
     reader.read_exact(&mut prime_size)?;
     if size != 32 + field_size as u64 {
@@ -56,7 +116,7 @@ fn read_header<R: Read>(mut reader: R, size: u64) -> Result<Header> {
         ));
     }
 
-    Ok(Header {
+    let header = Header {
         field_size,
         prime_size,
         n_wires: reader.read_u32::<LittleEndian>()?,
@@ -65,20 +125,54 @@ fn read_header<R: Read>(mut reader: R, size: u64) -> Result<Header> {
         n_prv_in: reader.read_u32::<LittleEndian>()?,
         n_labels: reader.read_u64::<LittleEndian>()?,
         n_constraints: reader.read_u32::<LittleEndian>()?,
-    })
+    };
+    if header.n_wires > limits.max_wires {
+        return Err(invalid_data(format!(
+            "{} wires exceed the limit of {}",
+            header.n_wires, limits.max_wires
+        )));
+    }
+    if header.n_constraints > limits.max_constraints {
+        return Err(invalid_data(format!(
+            "{} constraints exceed the limit of {}",
+            header.n_constraints, limits.max_constraints
+        )));
+    }
+    // private inputs that circom optimized away have no wire, so only the
+    // public signals are bounded
+    let n_public = 1 + header.n_pub_out as u64 + header.n_pub_in as u64;
+    if n_public > header.n_wires as u64 {
+        return Err(invalid_data("More public signals than wires"));
+    }
+    Ok(header)
 }
 
+/// Reads one linear combination, taking its size from `remaining`, the bytes
+/// left in the constraint section, and its terms from `terms_left`.
 fn read_constraint_vec<R: Read, Fr: PrimeField>(
     mut reader: R,
     header: &Header,
+    remaining: &mut u64,
+    terms_left: &mut u64,
 ) -> Result<Vec<(usize, Fr)>> {
-    let n_vec = reader.read_u32::<LittleEndian>()? as usize;
-    let mut vec = Vec::with_capacity(n_vec);
+    let n_vec = reader.read_u32::<LittleEndian>()? as u64;
+    let size = 4 + n_vec * TERM_SIZE;
+    if size > *remaining {
+        return Err(invalid_data("Invalid constraints section size"));
+    }
+    if n_vec > *terms_left {
+        return Err(invalid_data("Too many terms in constraints"));
+    }
+    *remaining -= size;
+    *terms_left -= n_vec;
+
+    let mut vec = Vec::with_capacity(n_vec as usize);
     for _ in 0..n_vec {
-        vec.push((
-            reader.read_u32::<LittleEndian>()? as usize,
-            read_field::<&mut R, Fr>(&mut reader)?,
-        ));
+        let wire = reader.read_u32::<LittleEndian>()?;
+        if wire >= header.n_wires {
+            return Err(invalid_data(format!("Constraint on unknown wire {}", wire)));
+        }
+        vec.push((wire as usize, read_field::<&mut R, Fr>(&mut reader)?));
     }
     Ok(vec)
 }
@@ -87,16 +181,40 @@ fn read_constraints<R: Read, Fr: PrimeField>(
     mut reader: R,
     size: u64,
     header: &Header,
+    limits: &ParseLimits,
 ) -> Result<Vec<Constraint<Fr>>> {
-    // todo check section size
+    // every constraint has at least the sizes of its three linear combinations
+    if header.n_constraints as u64 * 3 * 4 > size {
+        return Err(invalid_data("Invalid constraints section size"));
+    }
+    let mut remaining = size;
+    let mut terms_left = limits.max_terms;
     let mut vec = Vec::with_capacity(header.n_constraints as usize);
     for _ in 0..header.n_constraints {
         vec.push((
-            read_constraint_vec::<&mut R, Fr>(&mut reader, header)?,
-            read_constraint_vec::<&mut R, Fr>(&mut reader, header)?,
-            read_constraint_vec::<&mut R, Fr>(&mut reader, header)?,
+            read_constraint_vec::<&mut R, Fr>(
+                &mut reader,
+                header,
+                &mut remaining,
+                &mut terms_left,
+            )?,
+            read_constraint_vec::<&mut R, Fr>(
+                &mut reader,
+                header,
+                &mut remaining,
+                &mut terms_left,
+            )?,
+            read_constraint_vec::<&mut R, Fr>(
+                &mut reader,
+                header,
+                &mut remaining,
+                &mut terms_left,
+            )?,
         ));
     }
+    if remaining != 0 {
+        return Err(invalid_data("Invalid constraints section size"));
+    }
     Ok(vec)
 }
 
@@ -111,7 +229,7 @@ fn read_map<R: Read>(mut reader: R, size: u64, header: &Header) -> Result<Vec<u6
     for _ in 0..header.n_wires {
         vec.push(reader.read_u64::<LittleEndian>()?);
     }
-    if vec[0] != 0 {
+    if vec.first() != Some(&0) {
         return Err(Error::new(
             ErrorKind::InvalidData,
             "Wire 0 should always be mapped to 0",
@@ -120,7 +238,19 @@ fn read_map<R: Read>(mut reader: R, size: u64, header: &Header) -> Result<Vec<u6
     Ok(vec)
 }
 
-pub fn from_reader<R: Read + Seek>(mut reader: R) -> Result<R1CSFile<<G1 as Group>::Scalar>> {
+pub fn from_reader<R: Read + Seek>(reader: R) -> Result<R1CSFile<<G1 as Group>::Scalar>> {
+    from_reader_with_limits(reader, &ParseLimits::unlimited())
+}
+
+/// Parse a binary `.r1cs` file, rejecting it with an [`ErrorKind::InvalidData`]
+/// error if it is malformed or exceeds `limits`.
+pub fn from_reader_with_limits<R: Read + Seek>(
+    mut reader: R,
+    limits: &ParseLimits,
+) -> Result<R1CSFile<<G1 as Group>::Scalar>> {
+    let file_len = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(0))?;
+
     let mut magic = [0u8; 4];
     reader.read_exact(&mut magic)?;
     if magic != [0x72, 0x31, 0x63, 0x73] {
@@ -144,44 +274,46 @@ pub fn from_reader<R: Read + Seek>(mut reader: R) -> Result<R1CSFile<<G1 as Grou
         let section_type = reader.read_u32::<LittleEndian>()?;
         let section_size = reader.read_u64::<LittleEndian>()?;
         let offset = reader.seek(SeekFrom::Current(0))?;
+        if section_size > file_len - offset {
+            return Err(invalid_data(format!(
+                "Section {} extends past the end of the file",
+                section_type
+            )));
+        }
         section_offsets.insert(section_type, offset);
         section_sizes.insert(section_type, section_size);
-        reader.seek(SeekFrom::Current(section_size as i64))?;
+        reader.seek(SeekFrom::Start(offset + section_size))?;
     }
 
     let header_type = 1;
     let constraint_type = 2;
     let wire2label_type = 3;
 
-    reader.seek(SeekFrom::Start(*section_offsets.get(&header_type).unwrap()))?;
-    let header = read_header(&mut reader, *section_sizes.get(&header_type).unwrap())?;
-    if header.field_size != 32 {
-        return Err(Error::new(
-            ErrorKind::InvalidData,
-            "This parser only supports 32-byte fields",
-        ));
-    }
+    let section = |section_type: u32, name: &str| -> Result<(u64, u64)> {
+        match (
+            section_offsets.get(&section_type),
+            section_sizes.get(&section_type),
+        ) {
+            (Some(&offset), Some(&size)) => Ok((offset, size)),
+            _ => Err(invalid_data(format!("Missing {} section", name))),
+        }
+    };
+
+    let (offset, size) = section(header_type, "header")?;
+    reader.seek(SeekFrom::Start(offset))?;
+    let header = read_header(&mut reader, size, limits)?;
     // if header.prime_size != hex!("010000f093f5e1439170b97948e833285d588181b64550b829a031e1724e6430") {
     //     return Err(Error::new(ErrorKind::InvalidData, "This parser only supports bn256"));
     // }
 
-    reader.seek(SeekFrom::Start(
-        *section_offsets.get(&constraint_type).unwrap(),
-    ))?;
-    let constraints = read_constraints::<&mut R, <G1 as Group>::Scalar>(
-        &mut reader,
-        *section_sizes.get(&constraint_type).unwrap(),
-        &header,
-    )?;
-
-    reader.seek(SeekFrom::Start(
-        *section_offsets.get(&wire2label_type).unwrap(),
-    ))?;
-    let wire_mapping = read_map(
-        &mut reader,
-        *section_sizes.get(&wire2label_type).unwrap(),
-        &header,
-    )?;
+    let (offset, size) = section(constraint_type, "constraints")?;
+    reader.seek(SeekFrom::Start(offset))?;
+    let constraints =
+        read_constraints::<&mut R, <G1 as Group>::Scalar>(&mut reader, size, &header, limits)?;
+
+    let (offset, size) = section(wire2label_type, "wire to label map")?;
+    reader.seek(SeekFrom::Start(offset))?;
+    let wire_mapping = read_map(&mut reader, size, &header)?;
 
     Ok(R1CSFile {
         version,
@@ -285,7 +417,9 @@ mod tests {
         // fn read_header<R: Read>(mut reader: R, size: u64) -> Result<Header>
         let mut buf: Vec<u8> = 32_u32.to_le_bytes().to_vec();
         buf.resize(4 + 32, 0);
-        let err = read_header(&mut buf.as_slice(), 32).err().unwrap();
+        let err = read_header(&mut buf.as_slice(), 32, &ParseLimits::default())
+            .err()
+            .unwrap();
         assert_eq!(err.kind(), ErrorKind::InvalidData)
     }
 }
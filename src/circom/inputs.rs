@@ -0,0 +1,270 @@
+//! Check private inputs against the circuit's input signals before running the
+//! witness generator, whose own errors are hard to trace back to an input.
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    io::Cursor,
+    path::Path,
+};
+
+use serde_json::Value;
+
+use crate::{
+    circom::{
+        file::{from_reader_with_limits, Header, ParseLimits},
+        reader::load_r1cs_header,
+        sym::SymbolTable,
+    },
+    step_input::StepInputEncoding,
+};
+
+/// A problem with one signal of a step's input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InputProblem {
+    Missing(String),
+    Unknown(String),
+    WrongSize {
+        name: String,
+        /// array dimensions of the signal, empty for a single element
+        expected: Vec<usize>,
+    },
+}
+
+impl fmt::Display for InputProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputProblem::Missing(name) => write!(f, "missing input signal {}", name),
+            InputProblem::Unknown(name) => write!(f, "unknown input signal {}", name),
+            InputProblem::WrongSize { name, expected } if expected.is_empty() => {
+                write!(f, "input signal {} must be a single value", name)
+            }
+            InputProblem::WrongSize { name, expected } => {
+                write!(f, "input signal {} must be an array of size ", name)?;
+                for size in expected {
+                    write!(f, "[{}]", size)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Every problem found in the private input of `step`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InputError {
+    pub step: usize,
+    pub problems: Vec<InputProblem>,
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid private input for step {}: ", self.step)?;
+        for (i, problem) in self.problems.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for InputError {}
+
+impl From<InputError> for std::io::Error {
+    fn from(e: InputError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, e)
+    }
+}
+
+/// The input signals of a circuit's main component with their array dimensions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InputSignals {
+    pub signals: BTreeMap<String, Vec<usize>>,
+}
+
+impl InputSignals {
+    /// Take the input signals from `sym`. In circom's signal order, the main
+    /// component's `num_outputs` outputs come first, followed by its `num_inputs`
+    /// public and private inputs.
+    pub fn from_sym(sym: &SymbolTable, num_outputs: usize, num_inputs: usize) -> Self {
+        let mut signals: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for symbol in &sym.symbols {
+            let is_input = symbol.signal > num_outputs && symbol.signal <= num_outputs + num_inputs;
+            let (name, indices) = symbol.split_indices();
+            let name = match name.strip_prefix("main.") {
+                Some(name) if is_input && !name.contains('.') => name,
+                _ => continue,
+            };
+            let dims = signals
+                .entry(name.to_string())
+                .or_insert_with(|| vec![0; indices.len()]);
+            for (dim, index) in dims.iter_mut().zip(indices) {
+                *dim = (*dim).max(index + 1);
+            }
+        }
+        InputSignals { signals }
+    }
+
+    /// Read the input signals from a binary `.r1cs` file and its `.sym` file.
+    pub fn from_files(r1cs: &Path, sym: &Path) -> std::io::Result<Self> {
+        let header = load_r1cs_header(r1cs)?;
+        Ok(Self::from_header(&header, &SymbolTable::from_file(sym)?))
+    }
+
+    /// Same as [`from_files`](Self::from_files) with the contents of the files,
+    /// e.g. in the browser.
+    pub fn from_bytes(r1cs: &[u8], sym: &str) -> std::io::Result<Self> {
+        let header = from_reader_with_limits(Cursor::new(r1cs), &ParseLimits::default())?.header;
+        Ok(Self::from_header(&header, &SymbolTable::parse(sym)?))
+    }
+
+    fn from_header(header: &Header, sym: &SymbolTable) -> Self {
+        Self::from_sym(
+            sym,
+            header.n_pub_out as usize,
+            (header.n_pub_in + header.n_prv_in) as usize,
+        )
+    }
+
+    /// Check the private input of `step`, i.e. every input signal except the
+    /// ones of `step_input`, which hold the folded state.
+    pub fn check(
+        &self,
+        step: usize,
+        input: &HashMap<String, Value>,
+        step_input: &StepInputEncoding,
+    ) -> Result<(), InputError> {
+        let step_signals = step_input.signal_names();
+        let mut problems = vec![];
+        for (name, dims) in &self.signals {
+            if step_signals.contains(&name.as_str()) {
+                continue;
+            }
+            match input.get(name) {
+                None => problems.push(InputProblem::Missing(name.clone())),
+                Some(value) if !has_shape(value, dims) => problems.push(InputProblem::WrongSize {
+                    name: name.clone(),
+                    expected: dims.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        let mut unknown: Vec<&String> = input
+            .keys()
+            .filter(|name| {
+                !self.signals.contains_key(*name) || step_signals.contains(&name.as_str())
+            })
+            .collect();
+        unknown.sort();
+        problems.extend(
+            unknown
+                .into_iter()
+                .map(|name| InputProblem::Unknown(name.clone())),
+        );
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(InputError { step, problems })
+        }
+    }
+
+    /// [`check`](Self::check) the input of every step, stopping at the first bad one.
+    pub fn check_all(
+        &self,
+        inputs: &[HashMap<String, Value>],
+        step_input: &StepInputEncoding,
+    ) -> Result<(), InputError> {
+        for (step, input) in inputs.iter().enumerate() {
+            self.check(step, input, step_input)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `value` is nested like a signal of dimensions `dims`, or is a flat
+/// array of all of its elements, which circom also accepts.
+fn has_shape(value: &Value, dims: &[usize]) -> bool {
+    has_dims(value, dims)
+        || match value {
+            Value::Array(values) if dims.len() > 1 => {
+                values.len() == dims.iter().product::<usize>()
+                    && values
+                        .iter()
+                        .all(|v| matches!(v, Value::Number(_) | Value::String(_)))
+            }
+            _ => false,
+        }
+}
+
+fn has_dims(value: &Value, dims: &[usize]) -> bool {
+    match (value, dims.split_first()) {
+        (Value::Array(values), Some((size, rest))) => {
+            values.len() == *size && values.iter().all(|v| has_dims(v, rest))
+        }
+        (Value::Number(_) | Value::String(_), None) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::env::current_dir;
+
+    #[test]
+    fn test_check_toy_inputs() {
+        let root = current_dir().unwrap().join("examples/toy");
+        let signals =
+            InputSignals::from_files(&root.join("toy.r1cs"), &root.join("toy.sym")).unwrap();
+        assert_eq!(
+            signals.signals,
+            BTreeMap::from([
+                ("adder".to_string(), vec![]),
+                ("step_in".to_string(), vec![2])
+            ])
+        );
+
+        let input =
+            |value: Value| -> HashMap<String, Value> { serde_json::from_value(value).unwrap() };
+        let step_in = StepInputEncoding::default();
+        assert!(signals
+            .check(0, &input(json!({ "adder": 1 })), &step_in)
+            .is_ok());
+
+        let err = signals
+            .check(3, &input(json!({ "addr": 1 })), &step_in)
+            .unwrap_err();
+        assert_eq!(err.step, 3);
+        assert_eq!(
+            err.problems,
+            vec![
+                InputProblem::Missing("adder".to_string()),
+                InputProblem::Unknown("addr".to_string())
+            ]
+        );
+
+        let err = signals
+            .check(0, &input(json!({ "adder": [1, 2] })), &step_in)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid private input for step 0: input signal adder must be a single value"
+        );
+
+        let signals = InputSignals {
+            signals: BTreeMap::from([("m".to_string(), vec![2, 3])]),
+        };
+        for (value, ok) in [
+            (json!([[1, 2, 3], [4, 5, 6]]), true),
+            (json!([1, 2, 3, 4, 5, 6]), true),
+            (json!([1, 2, 3, 4, 5]), false),
+            (json!([[1, 2], [3, 4], [5, 6]]), false),
+        ] {
+            let result = signals.check(0, &input(json!({ "m": value })), &step_in);
+            assert_eq!(result.is_ok(), ok, "{}", value);
+        }
+    }
+}
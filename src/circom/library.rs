@@ -0,0 +1,192 @@
+//! In-process witness generation with circom's C++ witness generator built as a
+//! shared library by [`build_witness_library`](crate::circom::compile::build_witness_library).
+//!
+//! Unlike [`generate_witness_from_bin`](crate::circom::reader::generate_witness_from_bin),
+//! no process is spawned and no input or witness file is written: inputs are
+//! passed as field elements and the witness is returned in memory.
+//!
+//! A failing `assert` in the circuit aborts the calling process, as it does in
+//! circom's witness generator binary.
+use std::{
+    ffi::{CStr, CString},
+    io::{Error, ErrorKind},
+    os::raw::{c_char, c_void},
+    path::Path,
+};
+
+use ff::PrimeField;
+use libloading::Library;
+
+use crate::circom::file::read_field;
+
+type LoadFn = unsafe extern "C" fn(*const c_char, *mut c_char, usize) -> *mut c_void;
+type FreeFn = unsafe extern "C" fn(*mut c_void);
+type SizeFn = unsafe extern "C" fn(*const c_void) -> u32;
+type FieldBytesFn = unsafe extern "C" fn() -> u32;
+type CalculateFn = unsafe extern "C" fn(
+    *mut c_void,
+    u32,
+    *const *const c_char,
+    *const u32,
+    *const u8,
+    *mut u8,
+    *mut c_char,
+    usize,
+) -> i32;
+
+const FIELD_BYTES: usize = 32;
+const ERROR_LEN: usize = 1024;
+
+/// A circuit loaded from its witness generator library and `.dat` file.
+pub struct WitnessLibrary {
+    circuit: *mut c_void,
+    witness_size: usize,
+    calculate: CalculateFn,
+    free: FreeFn,
+    // keeps the functions above loaded, dropped after `circuit` is freed
+    _library: Library,
+}
+
+// The circuit is only read after loading, and every calculation creates its own
+// circom context.
+unsafe impl Send for WitnessLibrary {}
+unsafe impl Sync for WitnessLibrary {}
+
+fn library_error(e: libloading::Error) -> Error {
+    Error::other(e)
+}
+
+/// message written by the library to `error`
+fn error_message(error: &[c_char]) -> String {
+    unsafe { CStr::from_ptr(error.as_ptr()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+impl WitnessLibrary {
+    /// Open a witness generator library and the circuit's `.dat` file next to
+    /// it, e.g. `toy_cpp/toy.so` and `toy_cpp/toy.dat`.
+    pub fn open(library: impl AsRef<Path>) -> std::io::Result<Self> {
+        let library = library.as_ref();
+        Self::open_with_dat(library, &library.with_extension("dat"))
+    }
+
+    pub fn open_with_dat(library: &Path, dat: &Path) -> std::io::Result<Self> {
+        // circom's loadCircuit exits the process if it can't open the file
+        if !dat.exists() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("circuit data {} not found", dat.display()),
+            ));
+        }
+        let dat = CString::new(dat.to_string_lossy().as_bytes())
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+
+        unsafe {
+            let lib = Library::new(library).map_err(library_error)?;
+            let field_bytes: FieldBytesFn = *lib
+                .get(b"nova_scotia_field_bytes\0")
+                .map_err(library_error)?;
+            let load: LoadFn = *lib
+                .get(b"nova_scotia_circuit_load\0")
+                .map_err(library_error)?;
+            let free: FreeFn = *lib
+                .get(b"nova_scotia_circuit_free\0")
+                .map_err(library_error)?;
+            let witness_size: SizeFn = *lib
+                .get(b"nova_scotia_witness_size\0")
+                .map_err(library_error)?;
+            let calculate: CalculateFn = *lib
+                .get(b"nova_scotia_witness_calculate\0")
+                .map_err(library_error)?;
+
+            if field_bytes() as usize != FIELD_BYTES {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "witness generator uses {}-byte field elements, expected {}",
+                        field_bytes(),
+                        FIELD_BYTES
+                    ),
+                ));
+            }
+
+            let mut error = [0 as c_char; ERROR_LEN];
+            let circuit = load(dat.as_ptr(), error.as_mut_ptr(), ERROR_LEN);
+            if circuit.is_null() {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("failed to load circuit: {}", error_message(&error)),
+                ));
+            }
+            Ok(WitnessLibrary {
+                circuit,
+                witness_size: witness_size(circuit) as usize,
+                calculate,
+                free,
+                _library: lib,
+            })
+        }
+    }
+
+    /// number of elements in a witness
+    pub fn witness_size(&self) -> usize {
+        self.witness_size
+    }
+
+    /// Compute the witness for `inputs`, every input signal of the main
+    /// component with its elements flattened in circom's order.
+    pub fn calculate<Fr: PrimeField>(
+        &self,
+        inputs: &[(String, Vec<Fr>)],
+    ) -> std::io::Result<Vec<Fr>> {
+        let names = inputs
+            .iter()
+            .map(|(name, _)| CString::new(name.as_str()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+        let name_ptrs: Vec<*const c_char> = names.iter().map(|name| name.as_ptr()).collect();
+        let sizes: Vec<u32> = inputs
+            .iter()
+            .map(|(_, values)| values.len() as u32)
+            .collect();
+        let mut values = Vec::with_capacity(sizes.iter().sum::<u32>() as usize * FIELD_BYTES);
+        for (_, elements) in inputs {
+            for element in elements {
+                values.extend_from_slice(element.to_repr().as_ref());
+            }
+        }
+
+        let mut witness = vec![0u8; self.witness_size * FIELD_BYTES];
+        let mut error = [0 as c_char; ERROR_LEN];
+        let status = unsafe {
+            (self.calculate)(
+                self.circuit,
+                inputs.len() as u32,
+                name_ptrs.as_ptr(),
+                sizes.as_ptr(),
+                values.as_ptr(),
+                witness.as_mut_ptr(),
+                error.as_mut_ptr(),
+                ERROR_LEN,
+            )
+        };
+        if status != 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("witness generation failed: {}", error_message(&error)),
+            ));
+        }
+
+        witness
+            .chunks(FIELD_BYTES)
+            .map(read_field::<&[u8], Fr>)
+            .collect()
+    }
+}
+
+impl Drop for WitnessLibrary {
+    fn drop(&mut self) {
+        unsafe { (self.free)(self.circuit) }
+    }
+}
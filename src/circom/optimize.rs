@@ -0,0 +1,244 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+use ff::PrimeField;
+
+use crate::circom::circuit::{Constraint, R1CS};
+
+/// What [`optimize_r1cs`] removed. Every folding step synthesizes the whole
+/// step circuit, so the savings apply to each step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OptimizationReport {
+    pub constraints_before: usize,
+    pub constraints_after: usize,
+    pub duplicate_constraints: usize,
+    pub trivial_constraints: usize,
+    pub variables_before: usize,
+    pub variables_after: usize,
+}
+
+impl OptimizationReport {
+    /// constraints saved on every folding step
+    pub fn constraints_saved(&self) -> usize {
+        self.constraints_before - self.constraints_after
+    }
+
+    /// variables saved on every folding step
+    pub fn variables_saved(&self) -> usize {
+        self.variables_before - self.variables_after
+    }
+}
+
+impl fmt::Display for OptimizationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "constraints per step: {} -> {} ({} duplicate, {} trivial), variables per step: {} -> {}",
+            self.constraints_before,
+            self.constraints_after,
+            self.duplicate_constraints,
+            self.trivial_constraints,
+            self.variables_before,
+            self.variables_after
+        )
+    }
+}
+
+/// merge terms on the same wire, drop zero coefficients and sort by wire
+fn normalize<Fr: PrimeField>(lc: &[(usize, Fr)]) -> Vec<(usize, Fr)> {
+    let mut terms = BTreeMap::<usize, Fr>::new();
+    for (index, coeff) in lc {
+        *terms.entry(*index).or_insert_with(Fr::zero) += coeff;
+    }
+    terms
+        .into_iter()
+        .filter(|(_, coeff)| *coeff != Fr::zero())
+        .collect()
+}
+
+/// hashable representation of a normalized linear combination
+fn lc_key<Fr: PrimeField>(lc: &[(usize, Fr)]) -> Vec<(usize, Vec<u8>)> {
+    lc.iter()
+        .map(|(index, coeff)| (*index, coeff.to_repr().as_ref().to_vec()))
+        .collect()
+}
+
+/// constant term of a linear combination that only uses wire 0
+fn constant<Fr: PrimeField>(lc: &[(usize, Fr)]) -> Option<Fr> {
+    match lc {
+        [] => Some(Fr::zero()),
+        [(0, coeff)] => Some(*coeff),
+        _ => None,
+    }
+}
+
+/// `A * B = C` holds for every witness. Unsatisfiable constant constraints are
+/// kept so that proving still fails.
+fn is_trivial<Fr: PrimeField>(constraint: &Constraint<Fr>) -> bool {
+    let (a, b, c) = constraint;
+    if (a.is_empty() || b.is_empty()) && c.is_empty() {
+        return true;
+    }
+    match (constant(a), constant(b), constant(c)) {
+        (Some(a), Some(b), Some(c)) => a * b == c,
+        _ => false,
+    }
+}
+
+/// Remove duplicate and trivially satisfied constraints, and drop private
+/// wires no remaining constraint refers to.
+///
+/// Public inputs and outputs keep their positions, so the step circuit's I/O
+/// layout is unchanged. The returned R1CS records which witness value feeds
+/// each remaining wire, and `create_recursive_circuit` maps circom's witnesses
+/// accordingly, so it can be used in place of the original everywhere.
+pub fn optimize_r1cs<Fr: PrimeField>(r1cs: &R1CS<Fr>) -> (R1CS<Fr>, OptimizationReport) {
+    let mut seen = HashSet::new();
+    let mut duplicate_constraints = 0;
+    let mut trivial_constraints = 0;
+    let mut constraints = Vec::with_capacity(r1cs.constraints.len());
+
+    for (a, b, c) in &r1cs.constraints {
+        let (mut a, mut b, c) = (normalize(a), normalize(b), normalize(c));
+        if lc_key(&a) > lc_key(&b) {
+            // A * B = C is the same constraint as B * A = C
+            std::mem::swap(&mut a, &mut b);
+        }
+        let constraint = (a, b, c);
+        if is_trivial(&constraint) {
+            trivial_constraints += 1;
+            continue;
+        }
+        let key = (
+            lc_key(&constraint.0),
+            lc_key(&constraint.1),
+            lc_key(&constraint.2),
+        );
+        if !seen.insert(key) {
+            duplicate_constraints += 1;
+            continue;
+        }
+        constraints.push(constraint);
+    }
+
+    // wire 0 and the public inputs and outputs are always kept
+    let mut used = vec![false; r1cs.num_variables];
+    used[..r1cs.num_inputs].fill(true);
+    for (a, b, c) in &constraints {
+        for (index, _) in a.iter().chain(b).chain(c) {
+            used[*index] = true;
+        }
+    }
+
+    let mut new_index = vec![0; r1cs.num_variables];
+    let mut kept = Vec::with_capacity(r1cs.num_variables);
+    for (index, _) in used.iter().enumerate().filter(|&(_, &u)| u) {
+        new_index[index] = kept.len();
+        kept.push(index);
+    }
+
+    let remap = |lc: Vec<(usize, Fr)>| {
+        lc.into_iter()
+            .map(|(index, coeff)| (new_index[index], coeff))
+            .collect::<Vec<_>>()
+    };
+    let constraints: Vec<Constraint<Fr>> = constraints
+        .into_iter()
+        .map(|(a, b, c)| (remap(a), remap(b), remap(c)))
+        .collect();
+
+    let witness_indices: Vec<usize> = kept
+        .iter()
+        .map(|&index| match &r1cs.witness_indices {
+            None => index,
+            Some(indices) => indices[index],
+        })
+        .collect();
+
+    let report = OptimizationReport {
+        constraints_before: r1cs.constraints.len(),
+        constraints_after: constraints.len(),
+        duplicate_constraints,
+        trivial_constraints,
+        variables_before: r1cs.num_variables,
+        variables_after: kept.len(),
+    };
+
+    let optimized = R1CS {
+        num_inputs: r1cs.num_inputs,
+        num_aux: kept.len() - r1cs.num_inputs,
+        num_variables: kept.len(),
+        constraints,
+        witness_indices: Some(witness_indices),
+    };
+    (optimized, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pasta_curves::group::Group;
+
+    type F1 = <pasta_curves::pallas::Point as Group>::Scalar;
+
+    fn eval(lc: &[(usize, F1)], witness: &[F1]) -> F1 {
+        lc.iter().fold(F1::from(0), |acc, (index, coeff)| {
+            acc + *coeff * witness[*index]
+        })
+    }
+
+    fn is_satisfied(r1cs: &R1CS<F1>, witness: &[F1]) -> bool {
+        r1cs.constraints
+            .iter()
+            .all(|(a, b, c)| eval(a, witness) * eval(b, witness) == eval(c, witness))
+    }
+
+    #[test]
+    fn test_optimize_r1cs() {
+        let one = F1::from(1);
+        // wires: 0 = one, 1 = out, 2 = in, 3 = unused, 4 = square of in
+        let r1cs = R1CS {
+            num_inputs: 3,
+            num_aux: 2,
+            num_variables: 5,
+            constraints: vec![
+                (vec![(2, one)], vec![(2, one)], vec![(4, one)]),
+                // duplicate once the coefficients on wire 2 are merged
+                (
+                    vec![(2, one)],
+                    vec![(2, F1::from(2)), (2, -one)],
+                    vec![(4, one)],
+                ),
+                (vec![(4, one)], vec![(0, one)], vec![(1, one)]),
+                // trivially satisfied
+                (vec![], vec![(3, one)], vec![]),
+                (
+                    vec![(0, F1::from(2))],
+                    vec![(0, F1::from(3))],
+                    vec![(0, F1::from(6))],
+                ),
+            ],
+            witness_indices: None,
+        };
+        let witness = vec![one, F1::from(9), F1::from(3), F1::from(42), F1::from(9)];
+        assert!(is_satisfied(&r1cs, &witness));
+
+        let (optimized, report) = optimize_r1cs(&r1cs);
+        assert_eq!(report.constraints_saved(), 3);
+        assert_eq!(report.duplicate_constraints, 1);
+        assert_eq!(report.trivial_constraints, 2);
+        assert_eq!(report.variables_saved(), 1);
+        assert_eq!(optimized.num_inputs, 3);
+        assert_eq!(optimized.num_aux, 1);
+        assert_eq!(optimized.num_variables, 4);
+
+        let mapped = optimized.map_witness(witness);
+        assert_eq!(mapped, vec![one, F1::from(9), F1::from(3), F1::from(9)]);
+        assert!(is_satisfied(&optimized, &mapped));
+
+        // optimizing again keeps mapping from the original witness
+        let (again, report) = optimize_r1cs(&optimized);
+        assert_eq!(report.constraints_saved(), 0);
+        assert_eq!(again.witness_indices, optimized.witness_indices);
+    }
+}
@@ -1,59 +1,86 @@
 use anyhow::bail;
 use byteorder::{LittleEndian, ReadBytesExt};
-use itertools::Itertools;
 use std::collections::BTreeMap;
-use std::env::current_dir;
 use std::fs;
 use std::fs::{File, OpenOptions};
-use std::io::{BufReader, Read, Seek};
+use std::io::{BufReader, Cursor, Error, ErrorKind, Read, Seek};
 use std::path::Path;
-use std::process::Command;
-use std::str;
+use std::process::{Command, Output};
 
 use crate::circom::circuit::{CircuitJson, R1CS};
-use crate::circom::file::{from_reader, read_field};
+use crate::circom::file::{
+    from_reader, from_reader_with_limits, parse_field, read_field, Header, ParseLimits,
+};
 use crate::FileLocation;
 use ff::PrimeField;
 use pasta_curves::group::Group;
 
 type G1 = pasta_curves::pallas::Point;
 
+/// Errors returned by the witness generator helpers if the generator exits
+/// unsuccessfully carry its exit status and stderr.
+fn check_witness_generator_output(output: &Output) -> std::io::Result<()> {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    tracing::debug!(
+        stdout = %String::from_utf8_lossy(&output.stdout),
+        stderr = %stderr,
+        "witness generator finished with {}",
+        output.status
+    );
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Error::other(format!(
+            "witness generator failed with {}: {}",
+            output.status,
+            stderr.trim()
+        )))
+    }
+}
+
+/// Run circom's C++ witness generator binary. The input json is written next to
+/// `witness_output`.
+#[tracing::instrument(skip_all, name = "generate_witness")]
 pub fn generate_witness_from_bin<Fr: PrimeField>(
     witness_bin: &Path,
     witness_input_json: &String,
     witness_output: &Path,
-) -> Vec<Fr> {
-    let root = current_dir().unwrap();
-    let witness_generator_input = root.join("circom_input.json");
-    fs::write(&witness_generator_input, witness_input_json).unwrap();
+) -> std::io::Result<Vec<Fr>> {
+    let witness_generator_input = witness_output.with_extension("json");
+    fs::write(&witness_generator_input, witness_input_json)?;
 
     let output = Command::new(witness_bin)
         .arg(&witness_generator_input)
         .arg(witness_output)
-        .output()
-        .expect("failed to execute process");
-    if output.stdout.len() > 0 || output.stderr.len() > 0 {
-        print!("stdout: {}", str::from_utf8(&output.stdout).unwrap());
-        print!("stderr: {}", str::from_utf8(&output.stderr).unwrap());
-    }
+        .output();
     let _ = fs::remove_file(witness_generator_input);
-    load_witness_from_file(witness_output)
+    check_witness_generator_output(&output?)?;
+    Ok(load_witness_from_file(witness_output))
 }
 
 #[cfg(not(target_family = "wasm"))]
+/// Run circom's wasm witness generator with node. The input json is written next
+/// to `witness_output`.
+#[tracing::instrument(skip_all, name = "generate_witness")]
 pub fn generate_witness_from_wasm<Fr: PrimeField>(
     witness_wasm: &FileLocation,
     witness_input_json: &String,
     witness_output: &Path,
-) -> Vec<Fr> {
-    let witness_wasm = match witness_wasm {
-        FileLocation::PathBuf(path) => path,
+) -> std::io::Result<Vec<Fr>> {
+    let witness_wasm_location = witness_wasm;
+    let witness_wasm = match witness_wasm_location {
+        FileLocation::PathBuf(path) => path.clone(),
+        FileLocation::Bytes(bytes) => {
+            // node needs the witness generator on disk
+            let path = witness_output.with_extension("wasm");
+            fs::write(&path, bytes)?;
+            path
+        }
         FileLocation::URL(_) => panic!("unreachable"),
     };
 
-    let root = current_dir().unwrap();
-    let witness_generator_input = root.join("circom_input.json");
-    fs::write(&witness_generator_input, witness_input_json).unwrap();
+    let witness_generator_input = witness_output.with_extension("json");
+    fs::write(&witness_generator_input, witness_input_json)?;
 
     let witness_js = Path::new(concat!(
         env!("CARGO_MANIFEST_DIR"),
@@ -61,25 +88,115 @@ pub fn generate_witness_from_wasm<Fr: PrimeField>(
     ));
     let output = Command::new("node")
         .arg(witness_js)
-        .arg(witness_wasm)
+        .arg(&witness_wasm)
+        .arg(&witness_generator_input)
+        .arg(witness_output)
+        .output();
+    let _ = fs::remove_file(witness_generator_input);
+    if let FileLocation::Bytes(_) = witness_wasm_location {
+        let _ = fs::remove_file(witness_wasm);
+    }
+    check_witness_generator_output(&output?)?;
+    Ok(load_witness_from_file(witness_output))
+}
+
+#[cfg(all(not(target_family = "wasm"), feature = "async"))]
+/// async variant of [`generate_witness_from_bin`], running the witness generator
+/// as a tokio subprocess. The input json is written next to `witness_output`.
+#[tracing::instrument(skip_all, name = "generate_witness")]
+pub async fn generate_witness_from_bin_async<Fr: PrimeField>(
+    witness_bin: &Path,
+    witness_input_json: &String,
+    witness_output: &Path,
+) -> std::io::Result<Vec<Fr>> {
+    let witness_generator_input = witness_output.with_extension("json");
+    tokio::fs::write(&witness_generator_input, witness_input_json).await?;
+
+    let output = tokio::process::Command::new(witness_bin)
         .arg(&witness_generator_input)
         .arg(witness_output)
         .output()
-        .expect("failed to execute process");
-    if output.stdout.len() > 0 || output.stderr.len() > 0 {
-        print!("stdout: {}", str::from_utf8(&output.stdout).unwrap());
-        print!("stderr: {}", str::from_utf8(&output.stderr).unwrap());
+        .await;
+    let _ = tokio::fs::remove_file(witness_generator_input).await;
+    check_witness_generator_output(&output?)?;
+    load_witness_from_file_async(witness_output).await
+}
+
+#[cfg(all(not(target_family = "wasm"), feature = "async"))]
+/// async variant of [`generate_witness_from_wasm`], running node as a tokio subprocess.
+/// The input json is written next to `witness_output`.
+#[tracing::instrument(skip_all, name = "generate_witness")]
+pub async fn generate_witness_from_wasm_async<Fr: PrimeField>(
+    witness_wasm_location: &FileLocation,
+    witness_input_json: &String,
+    witness_output: &Path,
+) -> std::io::Result<Vec<Fr>> {
+    let witness_wasm = match witness_wasm_location {
+        FileLocation::PathBuf(path) => path.clone(),
+        FileLocation::Bytes(bytes) => {
+            // node needs the witness generator on disk
+            let path = witness_output.with_extension("wasm");
+            tokio::fs::write(&path, bytes).await?;
+            path
+        }
+        FileLocation::URL(_) => panic!("unreachable"),
+    };
+
+    let witness_generator_input = witness_output.with_extension("json");
+    tokio::fs::write(&witness_generator_input, witness_input_json).await?;
+
+    let witness_js = Path::new(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/circom/wasm_deps/generate_witness.js"
+    ));
+    let output = tokio::process::Command::new("node")
+        .arg(witness_js)
+        .arg(&witness_wasm)
+        .arg(&witness_generator_input)
+        .arg(witness_output)
+        .output()
+        .await;
+    let _ = tokio::fs::remove_file(witness_generator_input).await;
+    if let FileLocation::Bytes(_) = witness_wasm_location {
+        let _ = tokio::fs::remove_file(witness_wasm).await;
     }
-    let _ = fs::remove_file(witness_generator_input);
-    load_witness_from_file(witness_output)
+    check_witness_generator_output(&output?)?;
+    load_witness_from_file_async(witness_output).await
+}
+
+#[cfg(all(not(target_family = "wasm"), feature = "async"))]
+/// read a witness file without blocking the runtime, with autodetect encoding (bin or json)
+async fn load_witness_from_file_async<Fr: PrimeField>(filename: &Path) -> std::io::Result<Vec<Fr>> {
+    let contents = tokio::fs::read(filename).await?;
+    if contents.starts_with(&WTNS_MAGIC) {
+        load_witness_from_array(contents)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    } else {
+        Ok(load_witness_from_json(contents.as_slice()))
+    }
+}
+
+/// magic bytes at the start of a binary witness file
+const WTNS_MAGIC: [u8; 4] = *b"wtns";
+/// magic bytes at the start of a binary r1cs file
+const R1CS_MAGIC: [u8; 4] = *b"r1cs";
+
+/// check whether the file starts with the given magic bytes
+fn has_magic(filename: &Path, magic: &[u8; 4]) -> bool {
+    let mut file = File::open(filename).expect("unable to open.");
+    let mut header = [0u8; 4];
+    file.read_exact(&mut header).is_ok() && &header == magic
 }
 
 /// load witness file by filename with autodetect encoding (bin or json).
+///
+/// Binary `.wtns` files are recognised by their magic bytes, anything else is
+/// parsed as JSON (e.g. the output of `snarkjs wtns export json`).
 pub fn load_witness_from_file<Fr: PrimeField>(filename: &Path) -> Vec<Fr> {
-    if filename.ends_with("json") {
-        load_witness_from_json_file::<Fr>(filename)
-    } else {
+    if has_magic(filename, &WTNS_MAGIC) {
         load_witness_from_bin_file::<Fr>(filename)
+    } else {
+        load_witness_from_json_file::<Fr>(filename)
     }
 }
 
@@ -107,23 +224,34 @@ pub fn load_witness_from_bin_file<Fr: PrimeField>(filename: &Path) -> Vec<Fr> {
         .read(true)
         .open(filename)
         .expect("unable to open.");
-    load_witness_from_bin_reader::<Fr, BufReader<File>>(BufReader::new(reader))
-        .expect("read witness failed")
+    load_witness_from_bin_reader::<Fr, BufReader<File>>(
+        BufReader::new(reader),
+        &ParseLimits::unlimited(),
+    )
+    .expect("read witness failed")
 }
 
 /// load witness from u8 array
 pub fn load_witness_from_array<Fr: PrimeField>(buffer: Vec<u8>) -> Result<Vec<Fr>, anyhow::Error> {
-    load_witness_from_bin_reader::<Fr, _>(buffer.as_slice())
+    load_witness_from_array_with_limits(&buffer, &ParseLimits::unlimited())
+}
+
+/// load witness from untrusted bytes, rejecting witnesses that exceed `limits`
+pub fn load_witness_from_array_with_limits<Fr: PrimeField>(
+    buffer: &[u8],
+    limits: &ParseLimits,
+) -> Result<Vec<Fr>, anyhow::Error> {
+    load_witness_from_bin_reader::<Fr, _>(buffer, limits)
 }
 
 /// load witness from u8 array by a reader
 pub(crate) fn load_witness_from_bin_reader<Fr: PrimeField, R: Read>(
     mut reader: R,
+    limits: &ParseLimits,
 ) -> Result<Vec<Fr>, anyhow::Error> {
     let mut wtns_header = [0u8; 4];
     reader.read_exact(&mut wtns_header)?;
-    if wtns_header != [119, 116, 110, 115] {
-        // ruby -e 'p "wtns".bytes' => [119, 116, 110, 115]
+    if wtns_header != WTNS_MAGIC {
         bail!("invalid file header");
     }
     let version = reader.read_u32::<LittleEndian>()?;
@@ -155,15 +283,23 @@ pub(crate) fn load_witness_from_bin_reader<Fr: PrimeField, R: Read>(
     // }
     let witness_len = reader.read_u32::<LittleEndian>()?;
     // println!("witness len {}", witness_len);
+    if witness_len > limits.max_wires {
+        bail!(
+            "witness of {} elements exceeds the limit of {}",
+            witness_len,
+            limits.max_wires
+        );
+    }
     let sec_type = reader.read_u32::<LittleEndian>()?;
     if sec_type != 2 {
         bail!("invalid section type");
     }
     let sec_size = reader.read_u64::<LittleEndian>()?;
-    if sec_size != (witness_len * field_size) as u64 {
+    if sec_size != witness_len as u64 * field_size as u64 {
         bail!("invalid witness section size {}", sec_size);
     }
-    let mut result = Vec::with_capacity(witness_len as usize);
+    // the reader may be shorter than declared, so grow as elements are read
+    let mut result = Vec::with_capacity((witness_len as usize).min(1 << 16));
     for _ in 0..witness_len {
         result.push(read_field::<&mut R, Fr>(&mut reader)?);
     }
@@ -171,16 +307,44 @@ pub(crate) fn load_witness_from_bin_reader<Fr: PrimeField, R: Read>(
 }
 
 #[cfg(not(target_family = "wasm"))]
-/// load r1cs file by filename with autodetect encoding (bin or json)
+/// load r1cs file by filename with autodetect encoding (bin or json).
+///
+/// Binary `.r1cs` files are recognised by their magic bytes, anything else is
+/// parsed as JSON (e.g. the output of `snarkjs r1cs export json`).
+#[tracing::instrument(skip_all, name = "load_r1cs")]
 pub fn load_r1cs(filename: &FileLocation) -> R1CS<<G1 as Group>::Scalar> {
     let filename = match filename {
         FileLocation::PathBuf(filename) => filename,
+        FileLocation::Bytes(bytes) => return load_r1cs_from_bytes(bytes),
         FileLocation::URL(_) => panic!("unreachable"),
     };
-    if filename.ends_with("json") {
+    if has_magic(filename, &R1CS_MAGIC) {
+        load_r1cs_from_bin_file(filename)
+    } else {
         load_r1cs_from_json_file(filename)
+    }
+}
+
+/// load r1cs from an in-memory file with autodetect encoding (bin or json)
+pub fn load_r1cs_from_bytes(bytes: &[u8]) -> R1CS<<G1 as Group>::Scalar> {
+    if bytes.starts_with(&R1CS_MAGIC) {
+        load_r1cs_from_bin(Cursor::new(bytes))
     } else {
-        load_r1cs_from_bin_file(filename)
+        load_r1cs_from_json(bytes)
+    }
+}
+
+/// Load an r1cs (binary or JSON) from untrusted bytes. Unlike
+/// [`load_r1cs_from_bytes`], malformed files and files exceeding `limits` are
+/// returned as [`ErrorKind::InvalidData`] errors rather than panics.
+pub fn try_load_r1cs_from_bytes(
+    bytes: &[u8],
+    limits: &ParseLimits,
+) -> std::io::Result<R1CS<<G1 as Group>::Scalar>> {
+    if bytes.starts_with(&R1CS_MAGIC) {
+        read_r1cs_from_bin(Cursor::new(bytes), limits)
+    } else {
+        read_r1cs_from_json(bytes, limits)
     }
 }
 
@@ -198,35 +362,83 @@ fn load_r1cs_from_json_file<Fr: PrimeField>(filename: &Path) -> R1CS<Fr> {
 
 /// load r1cs from json by a reader
 fn load_r1cs_from_json<Fr: PrimeField, R: Read>(reader: R) -> R1CS<Fr> {
-    let circuit_json: CircuitJson = serde_json::from_reader(reader).expect("unable to read.");
+    read_r1cs_from_json(reader, &ParseLimits::unlimited()).expect("unable to read.")
+}
 
-    let num_inputs = circuit_json.num_inputs + circuit_json.num_outputs + 1;
-    let num_aux = circuit_json.num_variables - num_inputs;
+fn read_r1cs_from_json<Fr: PrimeField, R: Read>(
+    reader: R,
+    limits: &ParseLimits,
+) -> std::io::Result<R1CS<Fr>> {
+    let invalid = |message: String| Error::new(ErrorKind::InvalidData, message);
+    let circuit_json: CircuitJson = serde_json::from_reader(reader)?;
 
-    let convert_constraint = |lc: &BTreeMap<String, String>| {
+    let num_inputs = circuit_json
+        .num_inputs
+        .saturating_add(circuit_json.num_outputs)
+        .saturating_add(1);
+    let num_variables = circuit_json.num_variables;
+    if num_variables > limits.max_wires as usize {
+        return Err(invalid(format!(
+            "{} wires exceed the limit of {}",
+            num_variables, limits.max_wires
+        )));
+    }
+    if circuit_json.constraints.len() > limits.max_constraints as usize {
+        return Err(invalid(format!(
+            "{} constraints exceed the limit of {}",
+            circuit_json.constraints.len(),
+            limits.max_constraints
+        )));
+    }
+    let num_aux = num_variables
+        .checked_sub(num_inputs)
+        .ok_or_else(|| invalid("more input and output signals than wires".to_string()))?;
+
+    let convert_constraint = |lc: &BTreeMap<String, String>| -> std::io::Result<Vec<(usize, Fr)>> {
         lc.iter()
-            .map(|(index, coeff)| (index.parse().unwrap(), Fr::from_str_vartime(coeff).unwrap()))
-            .collect_vec()
+            .map(|(index, coeff)| {
+                let index: usize = index
+                    .parse()
+                    .ok()
+                    .filter(|&i| i < num_variables)
+                    .ok_or_else(|| invalid(format!("invalid wire {}", index)))?;
+                Ok((index, parse_field(coeff)?))
+            })
+            .collect()
     };
 
+    let mut terms = 0;
     let constraints = circuit_json
         .constraints
         .iter()
         .map(|c| {
-            (
-                convert_constraint(&c[0]),
-                convert_constraint(&c[1]),
-                convert_constraint(&c[2]),
-            )
+            if c.len() != 3 {
+                return Err(invalid(
+                    "a constraint must have three linear combinations".to_string(),
+                ));
+            }
+            terms += c.iter().map(|lc| lc.len() as u64).sum::<u64>();
+            if terms > limits.max_terms {
+                return Err(invalid(format!(
+                    "constraints exceed the limit of {} terms",
+                    limits.max_terms
+                )));
+            }
+            Ok((
+                convert_constraint(&c[0])?,
+                convert_constraint(&c[1])?,
+                convert_constraint(&c[2])?,
+            ))
         })
-        .collect_vec();
+        .collect::<std::io::Result<Vec<_>>>()?;
 
-    R1CS {
+    Ok(R1CS {
         num_inputs,
         num_aux,
-        num_variables: circuit_json.num_variables,
+        num_variables,
         constraints,
-    }
+        witness_indices: None,
+    })
 }
 
 /// load r1cs from bin file by filename
@@ -238,16 +450,208 @@ fn load_r1cs_from_bin_file(filename: &Path) -> R1CS<<G1 as Group>::Scalar> {
     load_r1cs_from_bin(BufReader::new(reader))
 }
 
+/// Header of a binary `.r1cs` file, e.g. for the number of private inputs,
+/// which the [`R1CS`] does not keep.
+#[cfg(not(target_family = "wasm"))]
+pub fn load_r1cs_header(filename: &Path) -> std::io::Result<Header> {
+    Ok(from_reader(BufReader::new(File::open(filename)?))?.header)
+}
+
 /// load r1cs from bin by a reader
 pub(crate) fn load_r1cs_from_bin<R: Read + Seek>(reader: R) -> R1CS<<G1 as Group>::Scalar> {
-    let file = from_reader(reader).expect("unable to read.");
+    read_r1cs_from_bin(reader, &ParseLimits::unlimited()).expect("unable to read.")
+}
+
+fn read_r1cs_from_bin<R: Read + Seek>(
+    reader: R,
+    limits: &ParseLimits,
+) -> std::io::Result<R1CS<<G1 as Group>::Scalar>> {
+    let file = from_reader_with_limits(reader, limits)?;
     let num_inputs = (1 + file.header.n_pub_in + file.header.n_pub_out) as usize;
     let num_variables = file.header.n_wires as usize;
     let num_aux = num_variables - num_inputs;
-    R1CS {
+    Ok(R1CS {
         num_aux,
         num_inputs,
         num_variables,
         constraints: file.constraints,
+        witness_indices: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::NamedTempFile;
+
+    type F1 = <G1 as Group>::Scalar;
+
+    /// a file of its own for every test, removed when dropped
+    fn write_tmp(suffix: &str, contents: &[u8]) -> NamedTempFile {
+        let file = tempfile::Builder::new()
+            .prefix("nova_scotia_")
+            .suffix(suffix)
+            .tempfile()
+            .unwrap();
+        fs::write(file.path(), contents).unwrap();
+        file
+    }
+
+    fn wtns_bytes(values: &[u64]) -> Vec<u8> {
+        let mut buf = WTNS_MAGIC.to_vec();
+        buf.extend(2u32.to_le_bytes());
+        buf.extend(2u32.to_le_bytes());
+        buf.extend(1u32.to_le_bytes());
+        buf.extend(40u64.to_le_bytes());
+        buf.extend(32u32.to_le_bytes());
+        buf.extend([0u8; 32]);
+        buf.extend((values.len() as u32).to_le_bytes());
+        buf.extend(2u32.to_le_bytes());
+        buf.extend((values.len() as u64 * 32).to_le_bytes());
+        for value in values {
+            let mut repr = [0u8; 32];
+            repr[..8].copy_from_slice(&value.to_le_bytes());
+            buf.extend(repr);
+        }
+        buf
+    }
+
+    #[test]
+    fn test_load_witness_bin() {
+        // the extension must not matter, only the magic bytes
+        let file = write_tmp(".json", &wtns_bytes(&[1, 20, 300]));
+        let witness = load_witness_from_file::<F1>(file.path());
+        assert_eq!(witness, vec![F1::from(1), F1::from(20), F1::from(300)]);
+    }
+
+    #[test]
+    fn test_load_witness_json() {
+        // `snarkjs wtns export json` format
+        let file = write_tmp(".json", br#"["1", "20", "300"]"#);
+        let witness = load_witness_from_file::<F1>(file.path());
+        assert_eq!(witness, vec![F1::from(1), F1::from(20), F1::from(300)]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_failing_witness_generator() {
+        let dir = tempfile::tempdir().unwrap();
+        let witness_output = dir.path().join("witness.wtns");
+        // sh runs the input json it is given as a script
+        let error = generate_witness_from_bin::<F1>(
+            Path::new("/bin/sh"),
+            &"echo 'invalid input' >&2; exit 3".to_string(),
+            &witness_output,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error.kind(), ErrorKind::Other);
+        let message = error.to_string();
+        assert!(message.contains("exit status: 3"), "{}", message);
+        assert!(message.ends_with("invalid input"), "{}", message);
+        assert!(!witness_output.with_extension("json").exists());
+    }
+
+    #[test]
+    fn test_load_r1cs_bin() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/toy/toy.r1cs");
+        let r1cs = load_r1cs(&FileLocation::PathBuf(path));
+        assert_eq!(r1cs.num_inputs, 5);
+        assert_eq!(r1cs.num_aux, 0);
+        assert_eq!(r1cs.num_variables, 5);
+        assert_eq!(r1cs.constraints.len(), 1);
+    }
+
+    #[test]
+    fn test_load_r1cs_bytes() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/toy/toy.r1cs");
+        let r1cs = load_r1cs(&FileLocation::Bytes(fs::read(path).unwrap()));
+        assert_eq!(r1cs.num_inputs, 5);
+        assert_eq!(r1cs.constraints.len(), 1);
+    }
+
+    #[test]
+    fn test_load_r1cs_json() {
+        // `snarkjs r1cs export json` format
+        let json = br#"{
+            "n8": 32,
+            "prime": "28948022309329048855892746252171976963363056481941647379679742748393362948097",
+            "curve": "vesta",
+            "nVars": 4,
+            "nOutputs": 1,
+            "nPubInputs": 1,
+            "nPrvInputs": 1,
+            "nLabels": 4,
+            "nConstraints": 1,
+            "useCustomGates": false,
+            "constraints": [[{"2": "1"}, {"3": "1"}, {"1": "1"}]],
+            "map": [0, 1, 2, 3],
+            "customGates": [],
+            "customGatesUses": []
+        }"#;
+        let file = write_tmp(".json", json);
+        let r1cs = load_r1cs(&FileLocation::PathBuf(file.path().to_path_buf()));
+        assert_eq!(r1cs.num_inputs, 3);
+        assert_eq!(r1cs.num_aux, 1);
+        assert_eq!(r1cs.num_variables, 4);
+        assert_eq!(r1cs.constraints.len(), 1);
+        assert_eq!(r1cs.constraints[0].0, vec![(2, F1::from(1))]);
+        assert_eq!(r1cs.constraints[0].2, vec![(1, F1::from(1))]);
+    }
+
+    #[test]
+    fn test_reject_malformed_files() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/toy/toy.r1cs");
+        let bytes = fs::read(path).unwrap();
+        let limits = ParseLimits::default();
+        assert!(try_load_r1cs_from_bytes(&bytes, &limits).is_ok());
+
+        let err = |bytes: &[u8], limits: &ParseLimits| {
+            try_load_r1cs_from_bytes(bytes, limits)
+                .err()
+                .unwrap()
+                .kind()
+        };
+        // coefficient of the first term of the first constraint, above the modulus
+        let mut non_canonical = bytes.clone();
+        non_canonical[40..72].fill(0xff);
+        assert_eq!(err(&non_canonical, &limits), ErrorKind::InvalidData);
+        // a section extending past the end of the file
+        assert_eq!(err(&bytes[..200], &limits), ErrorKind::InvalidData);
+        let small = ParseLimits {
+            max_constraints: 0,
+            ..limits
+        };
+        assert_eq!(err(&bytes, &small), ErrorKind::InvalidData);
+        let json = br#"{"nVars": 2, "nOutputs": 1, "nPubInputs": 1, "constraints": []}"#;
+        assert_eq!(err(json, &limits), ErrorKind::InvalidData);
+        // coefficients at or above the modulus, as in the binary format
+        let json_r1cs = |coeff: &str| {
+            format!(
+                r#"{{"nVars": 4, "nOutputs": 1, "nPubInputs": 1, "constraints": [[{{"2": "{}"}}, {{"3": "1"}}, {{"1": "1"}}]]}}"#,
+                coeff
+            )
+        };
+        let modulus =
+            "28948022309329048855892746252171976963363056481941647379679742748393362948097";
+        let below_modulus =
+            "28948022309329048855892746252171976963363056481941647379679742748393362948096";
+        for coeff in ["0", "1", below_modulus] {
+            assert!(try_load_r1cs_from_bytes(json_r1cs(coeff).as_bytes(), &limits).is_ok());
+        }
+        for coeff in [modulus, "01", "-1", ""] {
+            assert_eq!(
+                err(json_r1cs(coeff).as_bytes(), &limits),
+                ErrorKind::InvalidData
+            );
+        }
+
+        let wtns = wtns_bytes(&[1, 2, 3]);
+        let small = ParseLimits {
+            max_wires: 2,
+            ..limits
+        };
+        assert!(load_witness_from_array_with_limits::<F1>(&wtns, &small).is_err());
+        assert!(load_witness_from_array_with_limits::<F1>(&wtns[..60], &limits).is_err());
     }
 }
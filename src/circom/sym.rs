@@ -0,0 +1,250 @@
+//! Reader for circom's `.sym` files, which name every signal of a circuit.
+//!
+//! Each line is `signal index,witness index,component index,name`, with a
+//! witness index of `-1` for signals the compiler optimized away.
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    /// witness (signal) index of the signal before circom's optimizations,
+    /// with 0 the constant-one wire
+    pub signal: usize,
+    /// index of the signal in the witness, if it was kept
+    pub witness: Option<usize>,
+    pub component: usize,
+    /// full name, e.g. `main.hasher.out[3]`
+    pub name: String,
+}
+
+impl Symbol {
+    /// name without array indices and the indices, e.g. `main.h` and `[1, 2]`
+    /// for `main.h[1][2]`
+    pub fn split_indices(&self) -> (&str, Vec<usize>) {
+        split_indices(&self.name)
+    }
+}
+
+/// Only the trailing indices are split, so `main.hashers[0].out[1]` is the
+/// element `[1]` of `main.hashers[0].out`.
+pub(crate) fn split_indices(name: &str) -> (&str, Vec<usize>) {
+    let mut base = name;
+    let mut indices = vec![];
+    while let Some(rest) = base.strip_suffix(']') {
+        let start = match rest.rfind('[') {
+            Some(start) => start,
+            None => break,
+        };
+        match rest[start + 1..].parse() {
+            Ok(index) => indices.push(index),
+            Err(_) => break,
+        }
+        base = &rest[..start];
+    }
+    indices.reverse();
+    (base, indices)
+}
+
+/// All the signals of a circuit, in `.sym` file order.
+#[derive(Clone, Debug, Default)]
+pub struct SymbolTable {
+    pub symbols: Vec<Symbol>,
+    by_name: HashMap<String, usize>,
+}
+
+impl SymbolTable {
+    pub fn from_file(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(contents: &str) -> std::io::Result<Self> {
+        let mut table = SymbolTable::default();
+        for (i, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let invalid = || {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid sym file line {}: {}", i + 1, line),
+                )
+            };
+            let mut fields = line.splitn(4, ',');
+            let mut next = || fields.next().ok_or_else(invalid);
+            let signal = next()?.parse().map_err(|_| invalid())?;
+            let witness: i64 = next()?.parse().map_err(|_| invalid())?;
+            let component = next()?.parse().map_err(|_| invalid())?;
+            let name = next()?.to_string();
+
+            table.by_name.insert(name.clone(), table.symbols.len());
+            table.symbols.push(Symbol {
+                signal,
+                witness: usize::try_from(witness).ok(),
+                component,
+                name,
+            });
+        }
+        Ok(table)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Symbol> {
+        self.by_name.get(name).map(|i| &self.symbols[*i])
+    }
+}
+
+/// Values of a circuit's signals by name, from one witness computed by circom
+/// and the circuit's `.sym` file.
+///
+/// The witness must be in circom's order, as the witness generators return it
+/// and as [`ProgressObserver::step_witness`](crate::progress::ProgressObserver::step_witness)
+/// receives it, not reordered for an optimized R1CS.
+pub struct WitnessView<'a, Fr> {
+    symbols: &'a SymbolTable,
+    witness: &'a [Fr],
+}
+
+impl<'a, Fr: Copy> WitnessView<'a, Fr> {
+    pub fn new(symbols: &'a SymbolTable, witness: &'a [Fr]) -> Self {
+        WitnessView { symbols, witness }
+    }
+
+    fn value(&self, symbol: &Symbol) -> std::io::Result<Fr> {
+        let index = symbol.witness.ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("signal {} was optimized away by circom", symbol.name),
+            )
+        })?;
+        self.witness.get(index).copied().ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "signal {} is witness element {}, but the witness has {}",
+                    symbol.name,
+                    index,
+                    self.witness.len()
+                ),
+            )
+        })
+    }
+
+    /// value of a single signal, e.g. `main.hasher.out` or `main.blockHashes[3][1]`
+    pub fn get(&self, name: &str) -> std::io::Result<Fr> {
+        match self.symbols.get(name) {
+            Some(symbol) => self.value(symbol),
+            None if !self.array_symbols(name).is_empty() => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("signal {} is an array, use get_array", name),
+            )),
+            None => Err(Error::new(
+                ErrorKind::NotFound,
+                format!("no signal {} in the sym file", name),
+            )),
+        }
+    }
+
+    /// every element of an array signal such as `main.blockHashes`, flattened
+    /// in circom's order, i.e. `[0][0], [0][1], ..., [1][0], ...`
+    pub fn get_array(&self, name: &str) -> std::io::Result<Vec<Fr>> {
+        let symbols = self.array_symbols(name);
+        if symbols.is_empty() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("no array signal {} in the sym file", name),
+            ));
+        }
+        symbols
+            .into_iter()
+            .map(|(_, symbol)| self.value(symbol))
+            .collect()
+    }
+
+    /// elements of the array signal `name` with their indices, sorted
+    fn array_symbols(&self, name: &str) -> Vec<(Vec<usize>, &'a Symbol)> {
+        let mut symbols: Vec<_> = self
+            .symbols
+            .symbols
+            .iter()
+            .filter_map(|symbol| match symbol.split_indices() {
+                (base, indices) if base == name && !indices.is_empty() => Some((indices, symbol)),
+                _ => None,
+            })
+            .collect();
+        symbols.sort_by(|a, b| a.0.cmp(&b.0));
+        symbols
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sym() {
+        let table = SymbolTable::parse("1,1,0,main.step_out[0]\n5,-1,0,main.adder\n").unwrap();
+        assert_eq!(table.symbols.len(), 2);
+        assert_eq!(table.get("main.step_out[0]").unwrap().witness, Some(1));
+        assert_eq!(table.get("main.adder").unwrap().witness, None);
+        assert_eq!(
+            split_indices("main.blockHashes[3][1]"),
+            ("main.blockHashes", vec![3, 1])
+        );
+        assert_eq!(
+            split_indices("main.hashers[0].out[1]"),
+            ("main.hashers[0].out", vec![1])
+        );
+        assert_eq!(
+            split_indices("main.hashers[0].out"),
+            ("main.hashers[0].out", vec![])
+        );
+        assert!(SymbolTable::parse("1,x,0,main.a").is_err());
+    }
+
+    #[test]
+    fn test_witness_view() {
+        let table = SymbolTable::parse(
+            "1,1,0,main.out\n2,3,0,main.h[1][0]\n3,2,0,main.h[0][0]\n\
+             4,4,0,main.h[0][1]\n5,5,0,main.h[1][1]\n6,-1,1,main.adder.in\n",
+        )
+        .unwrap();
+        let witness = [1, 10, 20, 30, 40, 50];
+        let view = WitnessView::new(&table, &witness);
+        assert_eq!(view.get("main.out").unwrap(), 10);
+        assert_eq!(view.get("main.h[1][0]").unwrap(), 30);
+        assert_eq!(view.get_array("main.h").unwrap(), vec![20, 40, 30, 50]);
+        assert_eq!(
+            view.get("main.adder.in").unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+        assert_eq!(
+            view.get("main.h").unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+        assert!(view.get_array("main.out").is_err());
+
+        // an array of components, each with an array output
+        let table = SymbolTable::parse(
+            "1,1,0,main.hashers[0].out[0]\n2,2,0,main.hashers[0].out[1]\n\
+             3,3,1,main.hashers[1].out[0]\n4,4,1,main.hashers[1].out[1]\n",
+        )
+        .unwrap();
+        let view = WitnessView::new(&table, &witness);
+        assert_eq!(view.get_array("main.hashers[1].out").unwrap(), vec![30, 40]);
+        assert_eq!(view.get("main.hashers[0].out[1]").unwrap(), 20);
+        assert_eq!(
+            view.get("main.hashers[0].out").unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+        assert!(view.get_array("main.hashers").is_err());
+        // witness element 3 is past the end of a 3-element witness
+        let error = WitnessView::new(&table, &witness[..3])
+            .get("main.hashers[1].out[0]")
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert!(error.to_string().contains("witness element 3"), "{}", error);
+    }
+}
@@ -1,10 +1,12 @@
 use crate::{FileLocation, G1, R1CS};
 
-use crate::circom::reader::{load_r1cs_from_bin, load_witness_from_bin_reader};
+use crate::circom::{reader::load_r1cs_from_bytes, witness_calculator::WitnessCalculator};
+use crate::step_input::input_signals;
 use ff::PrimeField;
 use js_sys::Uint8Array;
 use nova_snark::traits::Group;
-use std::io::Cursor;
+use serde_json::Value;
+use std::collections::HashMap;
 use std::path::Path;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::JsFuture;
@@ -12,59 +14,52 @@ use wasm_bindgen_futures::JsFuture;
 #[wasm_bindgen(module = "/src/circom/wasm_deps/generate_witness_browser.js")]
 extern "C" {
     fn read_file_async(path: &str) -> JsValue;
-    fn generate_witness_browser_async(input_json_string: &str, wasm_file: &str) -> JsValue;
 }
 
 #[wasm_bindgen]
-pub async fn read_file(path: &str) -> Uint8Array {
+pub async fn read_file(path: &str) -> Result<Uint8Array, JsValue> {
     let promise_as_js_value = read_file_async(path);
     let promise = js_sys::Promise::from(promise_as_js_value);
     let future = JsFuture::from(promise);
-    let result: Result<JsValue, JsValue> = future.await;
-    if let Ok(content) = result {
-        return Uint8Array::new(&content);
-    } else {
-        return Uint8Array::new(&JsValue::NULL);
-    }
-}
-
-#[wasm_bindgen]
-pub async fn generate_witness_browser(input_json_string: &str, wasm_file: &str) -> Uint8Array {
-    let promise_as_js_value = generate_witness_browser_async(input_json_string, wasm_file);
-    let promise = js_sys::Promise::from(promise_as_js_value);
-    let future = JsFuture::from(promise);
-    let result: Result<JsValue, JsValue> = future.await;
-    if let Ok(content) = result {
-        return Uint8Array::new(&content);
-    } else {
-        return Uint8Array::new(&JsValue::NULL);
-    }
+    let content = future.await?;
+    Ok(Uint8Array::new(&content))
 }
 
 #[cfg(target_family = "wasm")]
 /// load r1cs file by filename with autodetect encoding (bin or json)
+#[tracing::instrument(skip_all, name = "load_r1cs")]
 pub async fn load_r1cs(filename: &FileLocation) -> R1CS<<G1 as Group>::Scalar> {
-    let filename = match filename {
+    match filename {
         FileLocation::PathBuf(_) => panic!("unreachable"),
-        FileLocation::URL(path) => path,
-    };
-    let r1cs_ser = read_file(filename).await.to_vec();
-    let r1cs_cursor = Cursor::new(r1cs_ser);
-    load_r1cs_from_bin(r1cs_cursor)
+        FileLocation::URL(path) => {
+            let content = read_file(path)
+                .await
+                .unwrap_or_else(|e| panic!("failed to fetch {}: {:?}", path, e));
+            load_r1cs_from_bytes(&content.to_vec())
+        }
+        FileLocation::Bytes(bytes) => load_r1cs_from_bytes(bytes),
+    }
 }
 
 #[cfg(target_family = "wasm")]
+/// Compute a witness with the circom wasm witness generator, instantiated from
+/// Rust with [`WitnessCalculator`]. Proofs over many steps should create the
+/// calculator once instead, as [`crate::create_recursive_circuit`] does.
+#[tracing::instrument(skip_all, name = "generate_witness")]
 pub async fn generate_witness_from_wasm<Fr: PrimeField>(
     witness_wasm: &FileLocation,
     witness_input_json: &String,
     _witness_output: &Path, // note: this is unused
-) -> Vec<Fr> {
-    let witness_wasm = match witness_wasm {
-        FileLocation::PathBuf(_) => panic!("unreachable"),
-        FileLocation::URL(path) => path,
-    };
-    let witness_output = generate_witness_browser(witness_input_json, witness_wasm).await;
-    let witness_output = witness_output.to_vec();
-    let witness_output = Cursor::new(witness_output);
-    load_witness_from_bin_reader(witness_output).expect("read witness failed")
+) -> std::io::Result<Vec<Fr>> {
+    let input: HashMap<String, Value> = serde_json::from_str(witness_input_json)?;
+    let calculator = WitnessCalculator::from_location(witness_wasm).await?;
+    calculator.calculate(&input_signals(&input)?)
+}
+
+/// message of a JS exception, falling back to its debug representation
+pub(crate) fn js_error_message(error: &JsValue) -> String {
+    match error.dyn_ref::<js_sys::Error>() {
+        Some(error) => String::from(error.message()),
+        None => error.as_string().unwrap_or_else(|| format!("{:?}", error)),
+    }
 }
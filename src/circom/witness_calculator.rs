@@ -0,0 +1,315 @@
+//! Circom's wasm witness generator, run from Rust through `js_sys::WebAssembly`.
+//!
+//! This follows circom's `witness_calculator.js` without going through it:
+//! inputs are written to the generator's shared memory as field elements, and
+//! the witness is read back the same way, with no `.wtns` encoding in between.
+use std::{
+    cell::RefCell,
+    io::{Error, ErrorKind},
+    rc::Rc,
+};
+
+use ff::PrimeField;
+use js_sys::{Function, Object, Reflect, WebAssembly};
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::{
+    circom::{
+        file::read_field,
+        wasm::{js_error_message, read_file},
+    },
+    FileLocation,
+};
+
+/// messages of the exception codes passed to the `exceptionHandler` import
+fn exception_message(code: u32) -> &'static str {
+    match code {
+        1 => "Signal not found.",
+        2 => "Too many signals set.",
+        3 => "Signal already set.",
+        4 => "Assert Failed.",
+        5 => "Not enough memory.",
+        6 => "Input signal array access exceeds the size.",
+        _ => "Unknown error.",
+    }
+}
+
+/// the same FNV-1a hash of signal names as circom
+fn fnv1a(name: &str) -> u64 {
+    name.bytes().fold(0xCBF29CE484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001B3)
+    })
+}
+
+fn js_error(error: JsValue) -> Error {
+    Error::new(ErrorKind::Other, js_error_message(&error))
+}
+
+struct Exports {
+    init: Function,
+    get_message_char: Function,
+    get_field_num_len32: Function,
+    get_raw_prime: Function,
+    read_shared_rw_memory: Function,
+    write_shared_rw_memory: Function,
+    get_input_signal_size: Function,
+    set_input_signal: Function,
+    get_input_size: Function,
+    get_witness_size: Function,
+    get_witness: Function,
+}
+
+impl Exports {
+    fn new(exports: &Object) -> Result<Self, JsValue> {
+        let get = |name: &str| -> Result<Function, JsValue> {
+            Reflect::get(exports, &name.into())?
+                .dyn_into::<Function>()
+                .map_err(|_| JsValue::from_str(&format!("missing export {}", name)))
+        };
+        Ok(Exports {
+            init: get("init")?,
+            get_message_char: get("getMessageChar")?,
+            get_field_num_len32: get("getFieldNumLen32")?,
+            get_raw_prime: get("getRawPrime")?,
+            read_shared_rw_memory: get("readSharedRWMemory")?,
+            write_shared_rw_memory: get("writeSharedRWMemory")?,
+            get_input_signal_size: get("getInputSignalSize")?,
+            set_input_signal: get("setInputSignal")?,
+            get_input_size: get("getInputSize")?,
+            get_witness_size: get("getWitnessSize")?,
+            get_witness: get("getWitness")?,
+        })
+    }
+
+    fn call(&self, f: &Function, args: &[JsValue]) -> Result<f64, JsValue> {
+        let args: js_sys::Array = args.iter().collect();
+        let result = f.apply(&JsValue::UNDEFINED, &args)?;
+        Ok(result.as_f64().unwrap_or_default())
+    }
+
+    fn message(&self) -> Result<String, JsValue> {
+        let mut message = String::new();
+        loop {
+            match self.call(&self.get_message_char, &[])? as u32 {
+                0 => return Ok(message),
+                c => message.push(char::from_u32(c).unwrap_or('?')),
+            }
+        }
+    }
+}
+
+/// An instance of a circom wasm witness generator, reused for every step.
+pub struct WitnessCalculator {
+    exports: Rc<Exports>,
+    /// messages printed by the generator before an exception
+    errors: Rc<RefCell<String>>,
+    n32: usize,
+    witness_size: usize,
+    // keep the imports alive as long as the instance
+    _imports: Vec<Closure<dyn FnMut(JsValue)>>,
+}
+
+impl WitnessCalculator {
+    /// Compile and instantiate the witness generator in `wasm`.
+    pub async fn new(wasm: &[u8]) -> std::io::Result<Self> {
+        Self::instantiate(wasm).await.map_err(js_error)
+    }
+
+    /// Fetch the witness generator if `location` is a URL, then instantiate it.
+    pub async fn from_location(location: &FileLocation) -> std::io::Result<Self> {
+        match location {
+            FileLocation::PathBuf(_) => panic!("unreachable"),
+            FileLocation::URL(path) => {
+                let wasm = read_file(path).await.map_err(js_error)?;
+                Self::new(&wasm.to_vec()).await
+            }
+            FileLocation::Bytes(bytes) => Self::new(bytes).await,
+        }
+    }
+
+    async fn instantiate(wasm: &[u8]) -> Result<Self, JsValue> {
+        let exports: Rc<RefCell<Option<Rc<Exports>>>> = Rc::new(RefCell::new(None));
+        let errors = Rc::new(RefCell::new(String::new()));
+
+        let exception_handler = {
+            let errors = errors.clone();
+            Closure::wrap(Box::new(move |code: JsValue| {
+                let message = format!(
+                    "{} {}",
+                    exception_message(code.as_f64().unwrap_or_default() as u32),
+                    errors.borrow().trim()
+                );
+                // unwinds through the witness generator back to the caller
+                wasm_bindgen::throw_str(&message);
+            }) as Box<dyn FnMut(JsValue)>)
+        };
+        let print_error_message = {
+            let (exports, errors) = (exports.clone(), errors.clone());
+            Closure::wrap(Box::new(move |_: JsValue| {
+                if let Some(exports) = exports.borrow().as_ref() {
+                    if let Ok(message) = exports.message() {
+                        errors.borrow_mut().push_str(&message);
+                        errors.borrow_mut().push('\n');
+                    }
+                }
+            }) as Box<dyn FnMut(JsValue)>)
+        };
+        let write_buffer_message = {
+            let exports = exports.clone();
+            Closure::wrap(Box::new(move |_: JsValue| {
+                if let Some(exports) = exports.borrow().as_ref() {
+                    if let Ok(message) = exports.message() {
+                        tracing::debug!("circuit log: {}", message);
+                    }
+                }
+            }) as Box<dyn FnMut(JsValue)>)
+        };
+        let show_shared_rw_memory =
+            Closure::wrap(Box::new(|_: JsValue| {}) as Box<dyn FnMut(JsValue)>);
+
+        let runtime = Object::new();
+        for (name, closure) in [
+            ("exceptionHandler", &exception_handler),
+            ("printErrorMessage", &print_error_message),
+            ("writeBufferMessage", &write_buffer_message),
+            ("showSharedRWMemory", &show_shared_rw_memory),
+        ] {
+            Reflect::set(&runtime, &name.into(), closure.as_ref())?;
+        }
+        let imports = Object::new();
+        Reflect::set(&imports, &"runtime".into(), &runtime)?;
+
+        let result = JsFuture::from(WebAssembly::instantiate_buffer(wasm, &imports)).await?;
+        let instance: WebAssembly::Instance =
+            Reflect::get(&result, &"instance".into())?.dyn_into()?;
+        let instance_exports = Rc::new(Exports::new(&instance.exports())?);
+        *exports.borrow_mut() = Some(instance_exports.clone());
+
+        let n32 = instance_exports.call(&instance_exports.get_field_num_len32, &[])? as usize;
+        let witness_size = instance_exports.call(&instance_exports.get_witness_size, &[])? as usize;
+        Ok(WitnessCalculator {
+            exports: instance_exports,
+            errors,
+            n32,
+            witness_size,
+            _imports: vec![
+                exception_handler,
+                print_error_message,
+                write_buffer_message,
+                show_shared_rw_memory,
+            ],
+        })
+    }
+
+    /// number of elements in a witness
+    pub fn witness_size(&self) -> usize {
+        self.witness_size
+    }
+
+    /// the generator's shared memory as a little-endian integer
+    fn read_shared_memory(&self) -> Result<Vec<u8>, JsValue> {
+        let mut bytes = Vec::with_capacity(self.n32 * 4);
+        for j in 0..self.n32 {
+            let limb = self.exports.call(
+                &self.exports.read_shared_rw_memory,
+                &[JsValue::from(j as u32)],
+            )? as u32;
+            bytes.extend_from_slice(&limb.to_le_bytes());
+        }
+        Ok(bytes)
+    }
+
+    /// Check that the generator was compiled for the prime of `Fr`.
+    fn check_prime<Fr: PrimeField>(&self) -> std::io::Result<()> {
+        let field_bytes = Fr::zero().to_repr().as_ref().len();
+        self.exports
+            .call(&self.exports.get_raw_prime, &[])
+            .map_err(js_error)?;
+        let mut prime_minus_one = self.read_shared_memory().map_err(js_error)?;
+        for byte in prime_minus_one.iter_mut() {
+            let (value, borrow) = byte.overflowing_sub(1);
+            *byte = value;
+            if !borrow {
+                break;
+            }
+        }
+        if self.n32 * 4 != field_bytes || prime_minus_one != (-Fr::one()).to_repr().as_ref() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "the witness generator was compiled for a different prime, use --prime vesta",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Compute the witness for `inputs`, every input signal of the main
+    /// component with its elements flattened in circom's order.
+    pub fn calculate<Fr: PrimeField>(
+        &self,
+        inputs: &[(String, Vec<Fr>)],
+    ) -> std::io::Result<Vec<Fr>> {
+        self.check_prime::<Fr>()?;
+        self.try_calculate(inputs).map_err(js_error)?
+    }
+
+    fn try_calculate<Fr: PrimeField>(
+        &self,
+        inputs: &[(String, Vec<Fr>)],
+    ) -> Result<std::io::Result<Vec<Fr>>, JsValue> {
+        let exports = &self.exports;
+        let invalid = |message: String| Ok(Err(Error::new(ErrorKind::InvalidInput, message)));
+
+        self.errors.borrow_mut().clear();
+        // sanity checks on, as witness_calculator.js does
+        exports.call(&exports.init, &[JsValue::from(1)])?;
+        let mut input_counter = 0;
+        for (name, values) in inputs {
+            let h = fnv1a(name);
+            let (msb, lsb) = (JsValue::from((h >> 32) as u32), JsValue::from(h as u32));
+            let size = exports.call(&exports.get_input_signal_size, &[msb.clone(), lsb.clone()])?;
+            if size < 0.0 {
+                return invalid(format!("signal {} not found", name));
+            }
+            if values.len() != size as usize {
+                return invalid(format!(
+                    "input signal {} has {} elements, not {}",
+                    name,
+                    size,
+                    values.len()
+                ));
+            }
+            for (i, value) in values.iter().enumerate() {
+                for (j, limb) in value.to_repr().as_ref().chunks(4).enumerate() {
+                    let limb = u32::from_le_bytes(limb.try_into().unwrap());
+                    exports.call(
+                        &exports.write_shared_rw_memory,
+                        &[JsValue::from(j as u32), JsValue::from(limb)],
+                    )?;
+                }
+                exports.call(
+                    &exports.set_input_signal,
+                    &[msb.clone(), lsb.clone(), JsValue::from(i as u32)],
+                )?;
+                input_counter += 1;
+            }
+        }
+        let input_size = exports.call(&exports.get_input_size, &[])? as usize;
+        if input_counter < input_size {
+            return invalid(format!(
+                "not all inputs have been set, only {} out of {}",
+                input_counter, input_size
+            ));
+        }
+
+        let mut witness = Vec::with_capacity(self.witness_size);
+        for i in 0..self.witness_size {
+            exports.call(&exports.get_witness, &[JsValue::from(i as u32)])?;
+            match read_field::<&[u8], Fr>(&self.read_shared_memory()?) {
+                Ok(x) => witness.push(x),
+                Err(e) => return Ok(Err(e)),
+            }
+        }
+        Ok(Ok(witness))
+    }
+}
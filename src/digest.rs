@@ -0,0 +1,214 @@
+use std::{fmt, io, str::FromStr};
+
+use ff::PrimeField;
+use nova_snark::PublicParams;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest as _, Sha256};
+
+use crate::{circom::circuit::R1CS, C1, C2, G1, G2};
+
+/// SHA-256 digest identifying a circuit or its public parameters.
+///
+/// Serialized as a lowercase hex string, so it stays readable in JSON proofs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Digest(pub [u8; 32]);
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Digest {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 64 || !s.is_ascii() {
+            return Err(format!("invalid digest length: {}", s));
+        }
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16)
+                .map_err(|_| format!("invalid digest: {}", s))?;
+        }
+        Ok(Digest(bytes))
+    }
+}
+
+impl Serialize for Digest {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Digest {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+/// `io::Write` adapter feeding everything written into the hasher
+struct HashWriter(Sha256);
+
+impl io::Write for HashWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<Fr: PrimeField> R1CS<Fr> {
+    /// Deterministic digest of the circuit layout, all of its constraints and
+    /// the mapping of its wires to circom's witness.
+    pub fn digest(&self) -> Digest {
+        let mut hasher = Sha256::new();
+        hasher.update(b"nova-scotia/r1cs/v2");
+        for count in [
+            self.num_inputs,
+            self.num_aux,
+            self.num_variables,
+            self.constraints.len(),
+        ] {
+            hasher.update((count as u64).to_le_bytes());
+        }
+        for (a, b, c) in &self.constraints {
+            for lc in [a, b, c] {
+                hasher.update((lc.len() as u64).to_le_bytes());
+                for (index, coeff) in lc {
+                    hasher.update((*index as u64).to_le_bytes());
+                    hasher.update(coeff.to_repr().as_ref());
+                }
+            }
+        }
+        match &self.witness_indices {
+            None => hasher.update([0]),
+            Some(indices) => {
+                hasher.update([1]);
+                hasher.update((indices.len() as u64).to_le_bytes());
+                for index in indices {
+                    hasher.update((*index as u64).to_le_bytes());
+                }
+            }
+        }
+        Digest(hasher.finalize().into())
+    }
+}
+
+/// Deterministic digest of the public parameters, computed over their serde
+/// serialization. This walks the whole commitment key, so compute it once and
+/// keep it around rather than calling it for every proof.
+pub fn params_digest(pp: &PublicParams<G1, G2, C1, C2>) -> Digest {
+    let mut writer = HashWriter(Sha256::new());
+    writer.0.update(b"nova-scotia/params/v1");
+    serde_json::to_writer(&mut writer, pp).expect("public params serialization failed");
+    Digest(writer.0.finalize().into())
+}
+
+/// Ties a proof to the circuit and public parameters it was made for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitFingerprint {
+    pub r1cs: Digest,
+    pub params: Digest,
+}
+
+impl CircuitFingerprint {
+    pub fn new(r1cs: &R1CS<crate::F1>, pp: &PublicParams<G1, G2, C1, C2>) -> Self {
+        CircuitFingerprint {
+            r1cs: r1cs.digest(),
+            params: params_digest(pp),
+        }
+    }
+}
+
+/// Public parameters with the fingerprint computed from them, to verify
+/// [`FingerprintedProof`]s against. The params digest is computed once, when it
+/// is created.
+pub struct FingerprintedParams {
+    pp: PublicParams<G1, G2, C1, C2>,
+    fingerprint: CircuitFingerprint,
+}
+
+impl FingerprintedParams {
+    pub fn new(r1cs: &R1CS<crate::F1>, pp: PublicParams<G1, G2, C1, C2>) -> Self {
+        let fingerprint = CircuitFingerprint::new(r1cs, &pp);
+        FingerprintedParams { pp, fingerprint }
+    }
+
+    pub fn pp(&self) -> &PublicParams<G1, G2, C1, C2> {
+        &self.pp
+    }
+
+    pub fn fingerprint(&self) -> &CircuitFingerprint {
+        &self.fingerprint
+    }
+}
+
+/// A proof serialized together with the fingerprint of its circuit, so
+/// verifiers can reject proofs for another circuit before checking them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FingerprintedProof<P> {
+    pub fingerprint: CircuitFingerprint,
+    pub proof: P,
+}
+
+impl<P> FingerprintedProof<P> {
+    pub fn new(fingerprint: CircuitFingerprint, proof: P) -> Self {
+        FingerprintedProof { fingerprint, proof }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::F1;
+
+    fn r1cs(coeff: u64) -> R1CS<F1> {
+        R1CS {
+            num_inputs: 3,
+            num_aux: 1,
+            num_variables: 4,
+            constraints: vec![(
+                vec![(2, F1::from(coeff))],
+                vec![(3, F1::from(1))],
+                vec![(1, F1::from(1))],
+            )],
+            witness_indices: None,
+        }
+    }
+
+    #[test]
+    fn test_r1cs_digest() {
+        assert_eq!(r1cs(1).digest(), r1cs(1).digest());
+        assert_ne!(r1cs(1).digest(), r1cs(2).digest());
+
+        let mut extra_wire = r1cs(1);
+        extra_wire.num_aux += 1;
+        extra_wire.num_variables += 1;
+        assert_ne!(r1cs(1).digest(), extra_wire.digest());
+
+        // same constraints, wires mapped to other witness elements
+        let mut remapped = r1cs(1);
+        remapped.witness_indices = Some(vec![0, 1, 2, 3]);
+        assert_ne!(r1cs(1).digest(), remapped.digest());
+        let mut swapped = r1cs(1);
+        swapped.witness_indices = Some(vec![0, 1, 3, 2]);
+        assert_ne!(remapped.digest(), swapped.digest());
+    }
+
+    #[test]
+    fn test_digest_serde() {
+        let digest = r1cs(1).digest();
+        let json = serde_json::to_string(&digest).unwrap();
+        assert_eq!(json, format!("\"{}\"", digest));
+        assert_eq!(serde_json::from_str::<Digest>(&json).unwrap(), digest);
+        assert!("abcd".parse::<Digest>().is_err());
+    }
+}
@@ -0,0 +1,209 @@
+//! Ethereum ABI encoding of folded proofs and their public inputs and outputs.
+//!
+//! There is no Solidity verifier for compressed SNARKs: they are Spartan proofs
+//! over the pasta curves, which the EVM has no precompiles for, so a contract
+//! can't check them at a practical gas cost. This module encodes what a
+//! contract receives regardless, e.g. one that trusts an off-chain verifier or
+//! forwards the proof to one.
+use std::io::{Error, ErrorKind};
+
+use ff::PrimeField;
+use nova_snark::CompressedSNARK;
+
+use crate::{C1, C2, F1, G1, G2, S1, S2};
+
+/// big-endian `uint256` word of a field element
+pub fn encode_uint256(x: &F1) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word.copy_from_slice(x.to_repr().as_ref());
+    // pasta field elements are represented in little-endian
+    word.reverse();
+    word
+}
+
+fn encode_usize(x: usize) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&(x as u64).to_be_bytes());
+    word
+}
+
+fn encode_array(z: &[F1], out: &mut Vec<u8>) {
+    out.extend_from_slice(&encode_usize(z.len()));
+    for x in z {
+        out.extend_from_slice(&encode_uint256(x));
+    }
+}
+
+fn encode_bytes(bytes: &[u8], out: &mut Vec<u8>) {
+    out.extend_from_slice(&encode_usize(bytes.len()));
+    out.extend_from_slice(bytes);
+    // padded to a whole number of words
+    out.resize(out.len() + (32 - bytes.len() % 32) % 32, 0);
+}
+
+/// ABI encoding of `(uint256 numSteps, uint256[] z0, uint256[] zn)`, to be
+/// appended to a function selector as calldata.
+pub fn encode_public_io(num_steps: usize, z0: &[F1], z_n: &[F1]) -> Vec<u8> {
+    let head_size = 3 * 32;
+    let z0_size = 32 * (1 + z0.len());
+
+    let mut out = Vec::with_capacity(head_size + z0_size + 32 * (1 + z_n.len()));
+    out.extend_from_slice(&encode_usize(num_steps));
+    out.extend_from_slice(&encode_usize(head_size));
+    out.extend_from_slice(&encode_usize(head_size + z0_size));
+    encode_array(z0, &mut out);
+    encode_array(z_n, &mut out);
+    out
+}
+
+/// Compact binary encoding of a compressed SNARK, as carried in calldata.
+/// Field elements and points take 32 bytes each.
+pub fn encode_compressed_proof(proof: &CompressedSNARK<G1, G2, C1, C2, S1, S2>) -> Vec<u8> {
+    bincode::serialize(proof).expect("compressed SNARK serialization failed")
+}
+
+/// Inverse of [`encode_compressed_proof`].
+pub fn decode_compressed_proof(
+    bytes: &[u8],
+) -> std::io::Result<CompressedSNARK<G1, G2, C1, C2, S1, S2>> {
+    bincode::deserialize(bytes)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, format!("invalid proof: {}", e)))
+}
+
+/// ABI encoding of `(uint256 numSteps, uint256[] z0, uint256[] zn, bytes proof)`,
+/// `proof` being the [`encode_compressed_proof`] encoding, to be appended to a
+/// function selector as calldata.
+pub fn encode_proof_calldata(
+    num_steps: usize,
+    z0: &[F1],
+    z_n: &[F1],
+    proof: &CompressedSNARK<G1, G2, C1, C2, S1, S2>,
+) -> Vec<u8> {
+    encode_calldata(num_steps, z0, z_n, &encode_compressed_proof(proof))
+}
+
+fn encode_calldata(num_steps: usize, z0: &[F1], z_n: &[F1], proof: &[u8]) -> Vec<u8> {
+    let head_size = 4 * 32;
+    let z0_size = 32 * (1 + z0.len());
+    let z_n_size = 32 * (1 + z_n.len());
+
+    let mut out = Vec::with_capacity(head_size + z0_size + z_n_size + 64 + proof.len());
+    out.extend_from_slice(&encode_usize(num_steps));
+    out.extend_from_slice(&encode_usize(head_size));
+    out.extend_from_slice(&encode_usize(head_size + z0_size));
+    out.extend_from_slice(&encode_usize(head_size + z0_size + z_n_size));
+    encode_array(z0, &mut out);
+    encode_array(z_n, &mut out);
+    encode_bytes(proof, &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethabi::{ParamType, Token};
+
+    fn uint256_array(z: &[F1]) -> Token {
+        Token::Array(
+            z.iter()
+                .map(|x| Token::Uint(encode_uint256(x).into()))
+                .collect(),
+        )
+    }
+
+    /// decoded by ethabi as a contract with this signature would
+    fn decode_calldata(calldata: &[u8]) -> Vec<Token> {
+        let uint256_array = ParamType::Array(Box::new(ParamType::Uint(256)));
+        ethabi::decode(
+            &[
+                ParamType::Uint(256),
+                uint256_array.clone(),
+                uint256_array,
+                ParamType::Bytes,
+            ],
+            calldata,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_encode_public_io() {
+        let encoded = encode_public_io(5, &[F1::from(1), F1::from(258)], &[F1::from(3)]);
+        let words: Vec<&[u8]> = encoded.chunks(32).collect();
+        assert_eq!(words.len(), 3 + 3 + 2);
+
+        let last_byte = |word: &[u8]| {
+            assert!(word[..30].iter().all(|b| *b == 0));
+            u16::from_be_bytes([word[30], word[31]])
+        };
+        let values: Vec<u16> = words.iter().map(|w| last_byte(w)).collect();
+        assert_eq!(values, vec![5, 96, 192, 2, 1, 258, 1, 3]);
+    }
+
+    #[test]
+    fn test_encode_calldata() {
+        let z0 = [F1::from(1), -F1::from(1)];
+        let z_n = [F1::from(3)];
+        for proof in [&[][..], &[7; 32], &[1, 2, 3]] {
+            let calldata = encode_calldata(5, &z0, &z_n, proof);
+            assert_eq!(calldata.len() % 32, 0);
+            assert_eq!(
+                decode_calldata(&calldata),
+                vec![
+                    Token::Uint(5.into()),
+                    uint256_array(&z0),
+                    uint256_array(&z_n),
+                    Token::Bytes(proof.to_vec()),
+                ]
+            );
+            assert_eq!(
+                ethabi::encode(&decode_calldata(&calldata)),
+                calldata,
+                "not the canonical encoding"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "prover", not(target_family = "wasm")))]
+    fn test_proof_calldata_round_trip() {
+        use crate::{
+            circom::reader::load_r1cs, compress_recursive_snark, create_public_params,
+            create_recursive_circuit, verify::verify_compressed_proof, FileLocation,
+        };
+        use serde_json::json;
+        use std::collections::HashMap;
+
+        let root = std::env::current_dir().unwrap().join("examples/toy");
+        let r1cs = load_r1cs(&FileLocation::PathBuf(root.join("toy.r1cs")));
+        let pp = create_public_params(r1cs.clone());
+        let z0 = vec![F1::from(10), F1::from(10)];
+        let recursive_snark = create_recursive_circuit(
+            FileLocation::PathBuf(root.join("toy_js/toy.wasm")),
+            r1cs,
+            vec![HashMap::from([("adder".to_string(), json!(1))])],
+            z0.clone(),
+            &pp,
+        )
+        .unwrap();
+        let proof = compress_recursive_snark(&pp, &recursive_snark).unwrap();
+        let z_n = vec![F1::from(11), F1::from(20)];
+
+        let tokens = decode_calldata(&encode_proof_calldata(1, &z0, &z_n, &proof));
+        assert_eq!(
+            tokens[..3],
+            [
+                Token::Uint(1.into()),
+                uint256_array(&z0),
+                uint256_array(&z_n)
+            ]
+        );
+        let bytes = match &tokens[3] {
+            Token::Bytes(bytes) => bytes,
+            token => panic!("expected bytes, got {:?}", token),
+        };
+        let decoded = decode_compressed_proof(bytes).unwrap();
+        assert_eq!(verify_compressed_proof(&pp, &decoded, 1, z0).unwrap(), z_n);
+        assert!(decode_compressed_proof(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
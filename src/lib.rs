@@ -1,29 +1,64 @@
-use std::{
-    collections::HashMap,
-    env::current_dir,
-    fs,
-    path::{Path, PathBuf},
-};
+use std::path::PathBuf;
 
 use circom::circuit::{CircomCircuit, R1CS};
 use nova_snark::{
+    errors::NovaError,
     traits::{circuit::TrivialTestCircuit, Group},
-    PublicParams, RecursiveSNARK,
+    CompressedSNARK, PublicParams, RecursiveSNARK,
 };
 use num_bigint::BigInt;
 use num_traits::Num;
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
 
-use crate::circom::reader::generate_witness_from_bin;
+#[cfg(feature = "prover")]
+use std::collections::HashMap;
+#[cfg(all(feature = "prover", not(target_family = "wasm")))]
+use std::{
+    env::current_dir,
+    fs,
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
-#[cfg(not(target_family = "wasm"))]
-use crate::circom::reader::generate_witness_from_wasm;
+#[cfg(feature = "prover")]
+use serde_json::Value;
 
-#[cfg(target_family = "wasm")]
-use crate::circom::wasm::generate_witness_from_wasm;
+#[cfg(feature = "prover")]
+use crate::progress::{cancelled_error, CancellationToken, ProgressObserver, Stopwatch};
+#[cfg(feature = "prover")]
+use crate::{circom::inputs::InputSignals, step_input::StepInputEncoding};
+
+#[cfg(all(feature = "prover", not(target_family = "wasm")))]
+use crate::circom::reader::{generate_witness_from_bin, generate_witness_from_wasm};
+#[cfg(all(feature = "prover", not(target_family = "wasm")))]
+use crate::step_input::circom_input_json;
+#[cfg(all(feature = "ffi", not(target_family = "wasm")))]
+use crate::{circom::library::WitnessLibrary, step_input::circom_input_signals};
+
+#[cfg(all(not(target_family = "wasm"), feature = "async"))]
+use crate::circom::reader::{generate_witness_from_bin_async, generate_witness_from_wasm_async};
+#[cfg(all(feature = "prover", any(target_family = "wasm", feature = "async")))]
+use std::sync::Arc;
+
+#[cfg(all(feature = "prover", target_family = "wasm"))]
+use crate::{
+    circom::witness_calculator::WitnessCalculator, progress::yield_to_event_loop,
+    step_input::circom_input_signals,
+};
 
+pub mod analysis;
 pub mod circom;
+pub mod digest;
+pub mod evm;
+#[cfg(all(feature = "prover", not(target_family = "wasm")))]
+pub mod manifest;
+pub mod progress;
+#[cfg(all(not(target_family = "wasm"), feature = "server"))]
+pub mod server;
+#[cfg(feature = "prover")]
+pub mod step_input;
+pub mod verify;
+#[cfg(all(target_family = "wasm", feature = "wasm"))]
+pub mod wasm_api;
 
 pub type G1 = pasta_curves::pallas::Point;
 pub type F1 = <G1 as Group>::Scalar;
@@ -40,8 +75,12 @@ type C2 = TrivialTestCircuit<<G2 as Group>::Scalar>;
 pub enum FileLocation {
     PathBuf(PathBuf),
     URL(String),
+    /// Contents of the file, e.g. cached in IndexedDB or bundled with the app.
+    /// Witness generators passed as bytes must be circom wasm.
+    Bytes(Vec<u8>),
 }
 
+#[tracing::instrument(skip_all, name = "setup")]
 pub fn create_public_params(
     r1cs: R1CS<F1>,
 ) -> PublicParams<G1, G2, CircomCircuit<F1>, TrivialTestCircuit<F2>> {
@@ -58,15 +97,190 @@ pub fn create_public_params(
     pp
 }
 
-#[derive(Serialize, Deserialize)]
-struct CircomInput {
-    step_in: Vec<String>,
+/// Deserialize public parameters serialized with serde_json, e.g. from a
+/// browser cache, without going through a `String`.
+pub fn public_params_from_bytes(
+    bytes: &[u8],
+) -> Result<PublicParams<G1, G2, C1, C2>, serde_json::Error> {
+    serde_json::from_slice(bytes)
+}
+
+/// Compress a recursive SNARK into a [`CompressedSNARK`] using Spartan with IPA-PC.
+#[tracing::instrument(skip_all, name = "compress")]
+pub fn compress_recursive_snark(
+    pp: &PublicParams<G1, G2, C1, C2>,
+    recursive_snark: &RecursiveSNARK<G1, G2, C1, C2>,
+) -> Result<CompressedSNARK<G1, G2, C1, C2, S1, S2>, NovaError> {
+    CompressedSNARK::prove(pp, recursive_snark)
+}
+
+/// decimal string representation of a field element, as circom expects in its input json
+fn to_decimal_string(x: &F1) -> String {
+    let hex = format!("{:?}", x);
+    BigInt::from_str_radix(hex.strip_prefix("0x").unwrap(), 16)
+        .unwrap()
+        .to_str_radix(10)
+}
 
-    #[serde(flatten)]
-    extra: HashMap<String, Value>,
+/// `prove_step` failing for `step`. The proving functions return it inside a
+/// [`std::io::Error`] of kind `Other`, from which it can be downcast.
+#[cfg(feature = "prover")]
+#[derive(Debug)]
+pub struct ProveStepError {
+    pub step: usize,
+    pub error: NovaError,
 }
 
-#[cfg(not(target_family = "wasm"))]
+#[cfg(feature = "prover")]
+impl std::fmt::Display for ProveStepError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "prove_step failed for step {}: {}",
+            self.step, self.error
+        )
+    }
+}
+
+#[cfg(feature = "prover")]
+impl std::error::Error for ProveStepError {}
+
+#[cfg(feature = "prover")]
+fn prove_step_error(step: usize, error: NovaError) -> std::io::Error {
+    std::io::Error::other(ProveStepError { step, error })
+}
+
+/// A witness file name of its own for every proof, so that concurrent proofs
+/// don't overwrite each other's witnesses.
+#[cfg(all(feature = "prover", not(target_family = "wasm")))]
+fn witness_output_path() -> std::io::Result<PathBuf> {
+    static PROOF_COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let proof_id = PROOF_COUNTER.fetch_add(1, Ordering::Relaxed);
+    Ok(current_dir()?.join(format!(
+        "circom_witness_{}_{}.wtns",
+        std::process::id(),
+        proof_id
+    )))
+}
+
+/// error returned when there are no private inputs, i.e. no step to prove
+#[cfg(feature = "prover")]
+fn no_steps_error() -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        "no steps to prove, private_inputs is empty",
+    )
+}
+
+/// Options of [`create_recursive_circuit_with_progress`]. The default passes
+/// `z_i` as `step_in`, checks nothing, reports to no observer and is never
+/// cancelled.
+///
+/// `O` is the observer's type, `dyn ProgressObserver + Send` for
+/// `create_recursive_circuit_async`.
+#[cfg(feature = "prover")]
+pub struct ProofOptions<'a, O: ?Sized = dyn ProgressObserver + 'a> {
+    /// how `z_i` is passed to the witness generator
+    pub step_input: StepInputEncoding,
+    /// the circuit's input signals, read from its `.sym` file, against which
+    /// every step's private input is checked before any witness is generated
+    pub inputs: Option<&'a InputSignals>,
+    /// receives an event for every witness and step
+    pub observer: Option<&'a mut O>,
+    /// stops the proof before its next step, with an error of kind
+    /// [`std::io::ErrorKind::Interrupted`]
+    pub cancellation: CancellationToken,
+}
+
+#[cfg(feature = "prover")]
+impl<'a, O: ?Sized> Default for ProofOptions<'a, O> {
+    fn default() -> Self {
+        ProofOptions {
+            step_input: StepInputEncoding::default(),
+            inputs: None,
+            observer: None,
+            cancellation: CancellationToken::new(),
+        }
+    }
+}
+
+#[cfg(feature = "prover")]
+impl<'a, O: ?Sized> ProofOptions<'a, O> {
+    /// checks done before the first step
+    fn check(
+        &self,
+        start_public_input: &[F1],
+        private_inputs: &[HashMap<String, Value>],
+    ) -> std::io::Result<()> {
+        self.step_input.check_arity(start_public_input.len())?;
+        if let Some(inputs) = self.inputs {
+            inputs.check_all(private_inputs, &self.step_input)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "prover", not(target_family = "wasm")))]
+fn is_wasm_witness_generator(witness_generator_file: &FileLocation) -> bool {
+    match witness_generator_file {
+        FileLocation::PathBuf(path) => path.extension().unwrap_or_default() == "wasm",
+        FileLocation::URL(_) | FileLocation::Bytes(_) => true,
+    }
+}
+
+/// The witness generator of a proof, set up once before its first step.
+#[cfg(all(feature = "prover", not(target_family = "wasm")))]
+enum WitnessGenerator<'a> {
+    Wasm(&'a FileLocation),
+    Bin(&'a Path),
+    #[cfg(feature = "ffi")]
+    Library(WitnessLibrary),
+}
+
+#[cfg(all(feature = "prover", not(target_family = "wasm")))]
+impl<'a> WitnessGenerator<'a> {
+    fn open(witness_generator_file: &'a FileLocation) -> std::io::Result<Self> {
+        if is_wasm_witness_generator(witness_generator_file) {
+            return Ok(WitnessGenerator::Wasm(witness_generator_file));
+        }
+        let path = match witness_generator_file {
+            FileLocation::PathBuf(path) => path,
+            FileLocation::URL(_) | FileLocation::Bytes(_) => panic!("unreachable"),
+        };
+        #[cfg(feature = "ffi")]
+        if path.extension().unwrap_or_default() == std::env::consts::DLL_EXTENSION {
+            return Ok(WitnessGenerator::Library(WitnessLibrary::open(path)?));
+        }
+        Ok(WitnessGenerator::Bin(path))
+    }
+
+    fn generate(
+        &self,
+        step_input: &StepInputEncoding,
+        step_in: &[F1],
+        private_input: &HashMap<String, Value>,
+        witness_generator_output: &Path,
+    ) -> std::io::Result<Vec<F1>> {
+        match self {
+            WitnessGenerator::Wasm(file) => generate_witness_from_wasm::<F1>(
+                file,
+                &circom_input_json(step_input, step_in, private_input),
+                witness_generator_output,
+            ),
+            WitnessGenerator::Bin(path) => generate_witness_from_bin::<F1>(
+                path,
+                &circom_input_json(step_input, step_in, private_input),
+                witness_generator_output,
+            ),
+            #[cfg(feature = "ffi")]
+            WitnessGenerator::Library(library) => {
+                library.calculate(&circom_input_signals(step_input, step_in, private_input)?)
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "prover", not(target_family = "wasm")))]
 pub fn create_recursive_circuit(
     witness_generator_file: FileLocation,
     r1cs: R1CS<F1>,
@@ -74,92 +288,198 @@ pub fn create_recursive_circuit(
     start_public_input: Vec<F1>,
     pp: &PublicParams<G1, G2, C1, C2>,
 ) -> Result<RecursiveSNARK<G1, G2, C1, C2>, std::io::Error> {
-    let root = current_dir().unwrap();
-    let witness_generator_output = root.join("circom_witness.wtns");
-
-    let iteration_count = private_inputs.len();
-    let mut circuit_iterations = Vec::with_capacity(iteration_count);
-
-    let start_public_input_hex = start_public_input
-        .iter()
-        .map(|&x| format!("{:?}", x).strip_prefix("0x").unwrap().to_string())
-        .collect::<Vec<String>>();
-    let mut current_public_input = start_public_input_hex.clone();
-
-    for i in 0..iteration_count {
-        let decimal_stringified_input: Vec<String> = current_public_input
-            .iter()
-            .map(|x| BigInt::from_str_radix(x, 16).unwrap().to_str_radix(10))
-            .collect();
+    create_recursive_circuit_with_progress(
+        witness_generator_file,
+        r1cs,
+        private_inputs,
+        start_public_input,
+        pp,
+        ProofOptions::default(),
+    )
+}
 
-        let input = CircomInput {
-            step_in: decimal_stringified_input.clone(),
-            extra: private_inputs[i].clone(),
-        };
+/// Same as [`create_recursive_circuit`], with the step input encoding, input
+/// checks, progress observer and cancellation of `options`.
+///
+/// Witness generation and `prove_step` are interleaved step by step, so the
+/// observer sees every step as soon as it is proved.
+///
+/// With the `ffi` feature, a witness generator with the platform's shared
+/// library extension (e.g. `toy_cpp/toy.so`) is loaded once and called in
+/// process, see [`circom::library`].
+#[cfg(all(feature = "prover", not(target_family = "wasm")))]
+pub fn create_recursive_circuit_with_progress(
+    witness_generator_file: FileLocation,
+    r1cs: R1CS<F1>,
+    private_inputs: Vec<HashMap<String, Value>>,
+    start_public_input: Vec<F1>,
+    pp: &PublicParams<G1, G2, C1, C2>,
+    options: ProofOptions,
+) -> Result<RecursiveSNARK<G1, G2, C1, C2>, std::io::Error> {
+    options.check(&start_public_input, &private_inputs)?;
+    let mut no_observer = ();
+    let observer = options.observer.unwrap_or(&mut no_observer);
+    let step_input = &options.step_input;
+    let cancellation = &options.cancellation;
+    let witness_generator = WitnessGenerator::open(&witness_generator_file)?;
+    let witness_generator_output = witness_output_path()?;
 
-        let input_json = serde_json::to_string(&input).unwrap();
+    let mut current_public_input = start_public_input.clone();
 
-        let is_wasm = match &witness_generator_file {
-            FileLocation::PathBuf(path) => path.extension().unwrap_or_default() == "wasm",
-            FileLocation::URL(_) => true,
-        };
+    let circuit_secondary = TrivialTestCircuit::default();
+
+    let mut recursive_snark: Option<RecursiveSNARK<G1, G2, C1, C2>> = None;
+
+    let z0_secondary = vec![<G2 as Group>::Scalar::zero()];
 
-        let witness = if is_wasm {
-            generate_witness_from_wasm::<<G1 as Group>::Scalar>(
-                &witness_generator_file,
-                &input_json,
+    let mut prove = || {
+        for (i, private_input) in private_inputs.iter().enumerate() {
+            if cancellation.is_cancelled() {
+                return Err(cancelled_error(i));
+            }
+
+            let stopwatch = Stopwatch::start();
+            let witness = witness_generator.generate(
+                step_input,
+                &current_public_input,
+                private_input,
                 &witness_generator_output,
-            )
-        } else {
-            let witness_generator_file = match &witness_generator_file {
-                FileLocation::PathBuf(path) => path,
-                FileLocation::URL(_) => panic!("unreachable"),
+            )?;
+            observer.witness_generated(i, stopwatch.elapsed());
+            observer.step_witness(i, &witness);
+
+            let circuit = CircomCircuit {
+                r1cs: r1cs.clone(),
+                witness: Some(r1cs.map_witness(witness)),
             };
-            generate_witness_from_bin::<<G1 as Group>::Scalar>(
-                &witness_generator_file,
-                &input_json,
-                &witness_generator_output,
-            )
-        };
-        let circuit = CircomCircuit {
-            r1cs: r1cs.clone(),
-            witness: Some(witness),
-        };
-        let current_public_output = circuit.get_public_outputs();
+            current_public_input = circuit.get_public_outputs();
+
+            let stopwatch = Stopwatch::start();
+            let res = tracing::info_span!("prove_step", step = i).in_scope(|| {
+                RecursiveSNARK::prove_step(
+                    pp,
+                    recursive_snark.take(),
+                    circuit,
+                    circuit_secondary.clone(),
+                    start_public_input.clone(),
+                    z0_secondary.clone(),
+                )
+            });
+
+            recursive_snark = Some(res.map_err(|e| prove_step_error(i, e))?);
+            observer.step_proved(i, stopwatch.elapsed());
+            observer.step_output(i, &current_public_input);
+        }
+        recursive_snark.take().ok_or_else(no_steps_error)
+    };
+    let result = prove();
+    // on every path, and not written at all by a witness generator library
+    let _ = fs::remove_file(&witness_generator_output);
+    result
+}
 
-        circuit_iterations.push(circuit);
-        current_public_input = current_public_output
-            .iter()
-            .map(|&x| format!("{:?}", x).strip_prefix("0x").unwrap().to_string())
-            .collect();
-    }
-    fs::remove_file(witness_generator_output)?;
+/// Async variant of [`create_recursive_circuit_with_progress`] for tokio
+/// applications.
+///
+/// Witness generation runs as async subprocesses and every `prove_step` is moved
+/// onto tokio's blocking thread pool, so the calling runtime is never blocked.
+/// Steps are generated and proved one after the other, with the same checks,
+/// events and cancellation as the blocking API. The observer must be `Send` so
+/// that the future can be spawned. The signature is identical on wasm targets,
+/// where it defers to the browser implementation of
+/// [`create_recursive_circuit_with_progress`].
+#[cfg(all(not(target_family = "wasm"), feature = "async"))]
+pub async fn create_recursive_circuit_async(
+    witness_generator_file: FileLocation,
+    r1cs: R1CS<F1>,
+    private_inputs: Vec<HashMap<String, Value>>,
+    start_public_input: Vec<F1>,
+    pp: Arc<PublicParams<G1, G2, C1, C2>>,
+    options: ProofOptions<'_, dyn ProgressObserver + Send + '_>,
+) -> Result<RecursiveSNARK<G1, G2, C1, C2>, std::io::Error> {
+    options.check(&start_public_input, &private_inputs)?;
+    let mut no_observer = ();
+    let observer = options.observer.unwrap_or(&mut no_observer);
+    let step_input = &options.step_input;
+    let witness_generator_output = witness_output_path()?;
 
-    let circuit_secondary = TrivialTestCircuit::default();
+    let mut current_public_input = start_public_input.clone();
 
     let mut recursive_snark: Option<RecursiveSNARK<G1, G2, C1, C2>> = None;
 
-    let z0_secondary = vec![<G2 as Group>::Scalar::zero()];
+    let result = async {
+        for (i, private_input) in private_inputs.iter().enumerate() {
+            if options.cancellation.is_cancelled() {
+                return Err(cancelled_error(i));
+            }
+
+            let stopwatch = Stopwatch::start();
+            let input_json = circom_input_json(step_input, &current_public_input, private_input);
+            let witness = if is_wasm_witness_generator(&witness_generator_file) {
+                generate_witness_from_wasm_async::<F1>(
+                    &witness_generator_file,
+                    &input_json,
+                    &witness_generator_output,
+                )
+                .await?
+            } else {
+                let witness_generator_file = match &witness_generator_file {
+                    FileLocation::PathBuf(path) => path,
+                    FileLocation::URL(_) | FileLocation::Bytes(_) => panic!("unreachable"),
+                };
+                generate_witness_from_bin_async::<F1>(
+                    witness_generator_file,
+                    &input_json,
+                    &witness_generator_output,
+                )
+                .await?
+            };
+            observer.witness_generated(i, stopwatch.elapsed());
+            observer.step_witness(i, &witness);
 
-    for i in 0..iteration_count {
-        let res = RecursiveSNARK::prove_step(
-            &pp,
-            recursive_snark,
-            circuit_iterations[i].clone(),
-            circuit_secondary.clone(),
-            start_public_input.clone(),
-            z0_secondary.clone(),
-        );
+            let circuit_primary = CircomCircuit {
+                r1cs: r1cs.clone(),
+                witness: Some(r1cs.map_witness(witness)),
+            };
+            current_public_input = circuit_primary.get_public_outputs();
+
+            let stopwatch = Stopwatch::start();
+            let pp = pp.clone();
+            let previous = recursive_snark.take();
+            let start_public_input = start_public_input.clone();
+            let span = tracing::info_span!("prove_step", step = i);
+            let res = tokio::task::spawn_blocking(move || {
+                let _enter = span.enter();
+                RecursiveSNARK::prove_step(
+                    &pp,
+                    previous,
+                    circuit_primary,
+                    TrivialTestCircuit::default(),
+                    start_public_input,
+                    vec![<G2 as Group>::Scalar::zero()],
+                )
+            })
+            .await
+            .map_err(std::io::Error::other)?;
 
-        assert!(res.is_ok());
-        recursive_snark = Some(res.unwrap());
+            recursive_snark = Some(res.map_err(|e| prove_step_error(i, e))?);
+            observer.step_proved(i, stopwatch.elapsed());
+            observer.step_output(i, &current_public_input);
+        }
+        recursive_snark.take().ok_or_else(no_steps_error)
     }
-
-    let recursive_snark = recursive_snark.unwrap();
+    .await;
+    // removed on every path, and never written without steps
+    let removed = match tokio::fs::remove_file(&witness_generator_output).await {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        removed => removed,
+    };
+    let recursive_snark = result?;
+    removed?;
     Ok(recursive_snark)
 }
 
-#[cfg(target_family = "wasm")]
+#[cfg(all(feature = "prover", target_family = "wasm"))]
 pub async fn create_recursive_circuit(
     witness_generator_file: FileLocation,
     r1cs: R1CS<F1>,
@@ -167,85 +487,184 @@ pub async fn create_recursive_circuit(
     start_public_input: Vec<F1>,
     pp: &PublicParams<G1, G2, C1, C2>,
 ) -> Result<RecursiveSNARK<G1, G2, C1, C2>, std::io::Error> {
-    let iteration_count = private_inputs.len();
-    let mut circuit_iterations = Vec::with_capacity(iteration_count);
-
-    let start_public_input_hex = start_public_input
-        .iter()
-        .map(|&x| format!("{:?}", x).strip_prefix("0x").unwrap().to_string())
-        .collect::<Vec<String>>();
-    let mut current_public_input = start_public_input_hex.clone();
-
-    for i in 0..iteration_count {
-        let decimal_stringified_input: Vec<String> = current_public_input
-            .iter()
-            .map(|x| BigInt::from_str_radix(x, 16).unwrap().to_str_radix(10))
-            .collect();
+    create_recursive_circuit_with_progress(
+        witness_generator_file,
+        r1cs,
+        private_inputs,
+        start_public_input,
+        pp,
+        ProofOptions::default(),
+    )
+    .await
+}
 
-        let input = CircomInput {
-            step_in: decimal_stringified_input.clone(),
-            extra: private_inputs[i].clone(),
-        };
+/// Same as [`create_recursive_circuit`], with the step input encoding, input
+/// checks, progress observer and cancellation of `options`.
+#[cfg(all(feature = "prover", target_family = "wasm"))]
+pub async fn create_recursive_circuit_with_progress(
+    witness_generator_file: FileLocation,
+    r1cs: R1CS<F1>,
+    private_inputs: Vec<HashMap<String, Value>>,
+    start_public_input: Vec<F1>,
+    pp: &PublicParams<G1, G2, C1, C2>,
+    options: ProofOptions,
+) -> Result<RecursiveSNARK<G1, G2, C1, C2>, std::io::Error> {
+    options.check(&start_public_input, &private_inputs)?;
+    let mut no_observer = ();
+    let observer = options.observer.unwrap_or(&mut no_observer);
+    let step_input = &options.step_input;
+    let cancellation = &options.cancellation;
+    // instantiated once and reused for every step
+    let calculator = WitnessCalculator::from_location(&witness_generator_file).await?;
+    let mut current_public_input = start_public_input.clone();
 
-        let input_json = serde_json::to_string(&input).unwrap();
+    let circuit_secondary = TrivialTestCircuit::default();
 
-        let is_wasm = match &witness_generator_file {
-            FileLocation::PathBuf(path) => path.extension().unwrap_or_default() == "wasm",
-            FileLocation::URL(_) => true,
-        };
+    let mut recursive_snark: Option<RecursiveSNARK<G1, G2, C1, C2>> = None;
+
+    let z0_secondary = vec![<G2 as Group>::Scalar::zero()];
+
+    for (i, private_input) in private_inputs.iter().enumerate() {
+        // proving never awaits otherwise, so a cancel message is only received here
+        yield_to_event_loop().await;
+        if cancellation.is_cancelled() {
+            return Err(cancelled_error(i));
+        }
+
+        let stopwatch = Stopwatch::start();
+        let witness = calculator.calculate(&circom_input_signals(
+            step_input,
+            &current_public_input,
+            private_input,
+        )?)?;
+        observer.witness_generated(i, stopwatch.elapsed());
+        observer.step_witness(i, &witness);
 
-        let witness = if is_wasm {
-            generate_witness_from_wasm::<<G1 as Group>::Scalar>(
-                &witness_generator_file,
-                &input_json,
-                Path::new(""),
-            )
-            .await
-        } else {
-            let witness_generator_file = match &witness_generator_file {
-                FileLocation::PathBuf(path) => path,
-                FileLocation::URL(_) => panic!("unreachable"),
-            };
-            generate_witness_from_bin::<<G1 as Group>::Scalar>(
-                &witness_generator_file,
-                &input_json,
-                Path::new(""),
-            )
-        };
         let circuit = CircomCircuit {
             r1cs: r1cs.clone(),
-            witness: Some(witness),
+            witness: Some(r1cs.map_witness(witness)),
         };
-        let current_public_output = circuit.get_public_outputs();
+        current_public_input = circuit.get_public_outputs();
+
+        let stopwatch = Stopwatch::start();
+        let res = tracing::info_span!("prove_step", step = i).in_scope(|| {
+            RecursiveSNARK::prove_step(
+                &pp,
+                recursive_snark,
+                circuit,
+                circuit_secondary.clone(),
+                start_public_input.clone(),
+                z0_secondary.clone(),
+            )
+        });
 
-        circuit_iterations.push(circuit);
-        current_public_input = current_public_output
-            .iter()
-            .map(|&x| format!("{:?}", x).strip_prefix("0x").unwrap().to_string())
-            .collect();
+        recursive_snark = Some(res.map_err(|e| prove_step_error(i, e))?);
+        observer.step_proved(i, stopwatch.elapsed());
+        observer.step_output(i, &current_public_input);
     }
 
-    let circuit_secondary = TrivialTestCircuit::default();
+    recursive_snark.ok_or_else(no_steps_error)
+}
 
-    let mut recursive_snark: Option<RecursiveSNARK<G1, G2, C1, C2>> = None;
+/// Browser counterpart of the native `create_recursive_circuit_async`, with the
+/// same signature so application code can be shared between targets.
+#[cfg(all(feature = "prover", target_family = "wasm"))]
+pub async fn create_recursive_circuit_async(
+    witness_generator_file: FileLocation,
+    r1cs: R1CS<F1>,
+    private_inputs: Vec<HashMap<String, Value>>,
+    start_public_input: Vec<F1>,
+    pp: Arc<PublicParams<G1, G2, C1, C2>>,
+    options: ProofOptions<'_, dyn ProgressObserver + Send + '_>,
+) -> Result<RecursiveSNARK<G1, G2, C1, C2>, std::io::Error> {
+    let options = ProofOptions {
+        step_input: options.step_input,
+        inputs: options.inputs,
+        observer: options
+            .observer
+            .map(|observer| observer as &mut dyn ProgressObserver),
+        cancellation: options.cancellation,
+    };
+    create_recursive_circuit_with_progress(
+        witness_generator_file,
+        r1cs,
+        private_inputs,
+        start_public_input,
+        &pp,
+        options,
+    )
+    .await
+}
 
-    let z0_secondary = vec![<G2 as Group>::Scalar::zero()];
+#[cfg(all(test, feature = "async", not(target_family = "wasm")))]
+mod tests {
+    use super::*;
+    use crate::{circom::reader::load_r1cs, verify::verify_recursive_proof};
+    use serde_json::json;
+    use std::env::current_dir;
 
-    for i in 0..iteration_count {
-        let res = RecursiveSNARK::prove_step(
-            &pp,
-            recursive_snark,
-            circuit_iterations[i].clone(),
-            circuit_secondary.clone(),
-            start_public_input.clone(),
-            z0_secondary.clone(),
-        );
+    #[derive(Default)]
+    struct StepOutputs(Vec<(usize, Vec<F1>)>);
 
-        assert!(res.is_ok());
-        recursive_snark = Some(res.unwrap());
+    impl ProgressObserver for StepOutputs {
+        fn step_output(&mut self, step: usize, z_i: &[F1]) {
+            self.0.push((step, z_i.to_vec()));
+        }
     }
 
-    let recursive_snark = recursive_snark.unwrap();
-
-    Ok(recursive_snark)
+    #[tokio::test]
+    async fn test_create_recursive_circuit_async_toy() {
+        let root = current_dir().unwrap().join("examples/toy");
+        let r1cs = load_r1cs(&FileLocation::PathBuf(root.join("toy.r1cs")));
+        let pp = Arc::new(create_public_params(r1cs.clone()));
+        let z0 = vec![F1::from(10), F1::from(10)];
+        let private_inputs: Vec<HashMap<String, Value>> = (0..2)
+            .map(|i| HashMap::from([("adder".to_string(), json!(i))]))
+            .collect();
+        let witness_generator = || FileLocation::PathBuf(root.join("toy_js/toy.wasm"));
+
+        // checked like the blocking API, before any witness is generated
+        let inputs =
+            InputSignals::from_files(&root.join("toy.r1cs"), &root.join("toy.sym")).unwrap();
+        let error = create_recursive_circuit_async(
+            witness_generator(),
+            r1cs.clone(),
+            vec![HashMap::from([("addr".to_string(), json!(0))])],
+            z0.clone(),
+            pp.clone(),
+            ProofOptions {
+                inputs: Some(&inputs),
+                ..ProofOptions::default()
+            },
+        )
+        .await
+        .err()
+        .unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+
+        let mut observer = StepOutputs::default();
+        let recursive_snark = create_recursive_circuit_async(
+            witness_generator(),
+            r1cs,
+            private_inputs,
+            z0.clone(),
+            pp.clone(),
+            ProofOptions {
+                observer: Some(&mut observer),
+                ..ProofOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let z_2 = vec![F1::from(11), F1::from(30)];
+        assert_eq!(
+            observer.0,
+            vec![(0, vec![F1::from(10), F1::from(20)]), (1, z_2.clone())]
+        );
+        assert_eq!(
+            verify_recursive_proof(&pp, &recursive_snark, 2, z0).unwrap(),
+            z_2
+        );
+    }
 }
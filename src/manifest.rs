@@ -0,0 +1,388 @@
+//! Circuit manifests: one JSON file describing a circuit's artifacts and I/O.
+//!
+//! ```json
+//! {
+//!   "r1cs": "toy.r1cs",
+//!   "witness_generator": { "kind": "cpp", "path": "toy_cpp/toy" },
+//!   "sym": "toy.sym",
+//!   "prime": "vesta",
+//!   "step_input": "step_in",
+//!   "arity": 2,
+//!   "private_inputs": { "adder": [] },
+//!   "params_cache": "toy.params.json"
+//! }
+//! ```
+//!
+//! Paths are relative to the manifest. `private_inputs` maps every private input
+//! signal to its array dimensions, `[]` for a single field element. Each step's
+//! input is checked against it before proving, and it must match the input
+//! signals of the `.sym` file, if given.
+//!
+//! The public parameters in `params_cache` are stored with the digests of the
+//! r1cs and of the parameters themselves, and are recreated when the circuit
+//! changes.
+//!
+//! Circuits taking `z_i` in several signals list them, in public input order, as
+//! `"step_signals": [{ "name": "root", "size": 1 }, { "name": "path", "size": 2 }]`
+//! instead of `step_input`. If the outputs are not declared in the order of the
+//! step input, `"output_order": [1, 0, 2]` makes output `order[k]` the `k`-th
+//! element of `z_{i+1}`.
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use nova_snark::{PublicParams, RecursiveSNARK};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    circom::{circuit::R1CS, inputs::InputSignals, reader::load_r1cs},
+    create_public_params, create_recursive_circuit_with_progress,
+    digest::{params_digest, CircuitFingerprint},
+    step_input::{StepInputEncoding, StepSignal},
+    FileLocation, ProofOptions, C1, C2, F1, G1, G2,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WitnessGeneratorKind {
+    /// binary built from circom's `--c` output
+    Cpp,
+    /// circom's `--wasm` output, run with node
+    Wasm,
+    /// circom's `--c` output built as a shared library, called in process with
+    /// the `ffi` feature
+    Library,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WitnessGeneratorManifest {
+    pub kind: WitnessGeneratorKind,
+    pub path: PathBuf,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CircuitManifest {
+    pub r1cs: PathBuf,
+    pub witness_generator: WitnessGeneratorManifest,
+    /// circom `.sym` file. When set, private inputs are checked against the
+    /// circuit's input signals before proving, which needs a binary r1cs.
+    #[serde(default)]
+    pub sym: Option<PathBuf>,
+    /// prime the circuit was compiled with, i.e. circom's `--prime`
+    pub prime: String,
+    /// name of the input signal holding `z_i`
+    #[serde(default = "default_step_input")]
+    pub step_input: String,
+    /// signals holding `z_i`, overriding `step_input` when not empty
+    #[serde(default)]
+    pub step_signals: Vec<StepSignal>,
+    /// circuit output for each element of `z_{i+1}`, by default in declaration order
+    #[serde(default)]
+    pub output_order: Option<Vec<usize>>,
+    /// number of field elements in `z_i`
+    pub arity: usize,
+    /// private input signals with their array dimensions
+    #[serde(default)]
+    pub private_inputs: BTreeMap<String, Vec<usize>>,
+    /// where to cache the serialized public parameters
+    #[serde(default)]
+    pub params_cache: Option<PathBuf>,
+}
+
+fn default_step_input() -> String {
+    "step_in".to_string()
+}
+
+impl CircuitManifest {
+    pub fn from_file(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    pub fn step_input_encoding(&self) -> StepInputEncoding {
+        if self.step_signals.is_empty() {
+            StepInputEncoding::Array(self.step_input.clone())
+        } else {
+            StepInputEncoding::Signals(self.step_signals.clone())
+        }
+    }
+}
+
+/// Everything needed to prove a circuit, loaded from a [`CircuitManifest`].
+pub struct Circuit {
+    pub manifest: CircuitManifest,
+    pub r1cs: R1CS<F1>,
+    pub pp: PublicParams<G1, G2, C1, C2>,
+    pub step_input: StepInputEncoding,
+    /// input signals read from the `.sym` file, or else the manifest's
+    /// `private_inputs`
+    pub inputs: InputSignals,
+    witness_generator: PathBuf,
+}
+
+fn invalid_manifest(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+/// Contents of a `params_cache` file: public parameters with the fingerprint of
+/// the circuit they were created for.
+#[derive(Serialize, Deserialize)]
+struct ParamsCache<P> {
+    fingerprint: CircuitFingerprint,
+    params: P,
+}
+
+/// Public parameters from `cache` if they were created for `r1cs`, or created
+/// and written to `cache`, e.g. after the circuit was recompiled.
+fn load_or_create_params(
+    cache: &Path,
+    r1cs: &R1CS<F1>,
+) -> std::io::Result<PublicParams<G1, G2, C1, C2>> {
+    let r1cs_digest = r1cs.digest();
+    match fs::read(cache) {
+        Ok(bytes) => {
+            match serde_json::from_slice::<ParamsCache<PublicParams<G1, G2, C1, C2>>>(&bytes) {
+                Ok(cached)
+                    if cached.fingerprint.r1cs == r1cs_digest
+                        && cached.fingerprint.params == params_digest(&cached.params) =>
+                {
+                    return Ok(cached.params)
+                }
+                Ok(_) => tracing::info!(
+                    "{} does not match the circuit, recreating it",
+                    cache.display()
+                ),
+                Err(e) => tracing::info!(
+                    "{} is not a params cache ({}), recreating it",
+                    cache.display(),
+                    e
+                ),
+            }
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    let pp = create_public_params(r1cs.clone());
+    let fingerprint = CircuitFingerprint {
+        r1cs: r1cs_digest,
+        params: params_digest(&pp),
+    };
+    fs::write(
+        cache,
+        serde_json::to_vec(&ParamsCache {
+            fingerprint,
+            params: &pp,
+        })?,
+    )?;
+    Ok(pp)
+}
+
+impl Circuit {
+    /// Load the circuit described by the manifest at `path`.
+    ///
+    /// Public parameters are read from `params_cache` if it was written for the
+    /// same r1cs, and created and written there otherwise.
+    pub fn from_manifest(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        let manifest = CircuitManifest::from_file(path)?;
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+
+        if manifest.prime != "vesta" {
+            return Err(invalid_manifest(format!(
+                "unsupported prime {}, circuits must be compiled with --prime vesta",
+                manifest.prime
+            )));
+        }
+
+        let step_input = manifest.step_input_encoding();
+        step_input
+            .check_arity(manifest.arity)
+            .map_err(|e| invalid_manifest(e.to_string()))?;
+
+        let witness_generator = base.join(&manifest.witness_generator.path);
+        let extension = witness_generator.extension().unwrap_or_default();
+        let kind = if extension == "wasm" {
+            WitnessGeneratorKind::Wasm
+        } else if extension == std::env::consts::DLL_EXTENSION {
+            WitnessGeneratorKind::Library
+        } else {
+            WitnessGeneratorKind::Cpp
+        };
+        if kind != manifest.witness_generator.kind {
+            return Err(invalid_manifest(format!(
+                "witness generator {} does not match its kind {:?}",
+                witness_generator.display(),
+                manifest.witness_generator.kind
+            )));
+        }
+        if kind == WitnessGeneratorKind::Library && !cfg!(feature = "ffi") {
+            return Err(invalid_manifest(
+                "library witness generators need the ffi feature".to_string(),
+            ));
+        }
+
+        let r1cs_path = base.join(&manifest.r1cs);
+        if !r1cs_path.exists() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("r1cs file {} not found", r1cs_path.display()),
+            ));
+        }
+        let r1cs = load_r1cs(&FileLocation::PathBuf(r1cs_path.clone()));
+        if r1cs.num_inputs != 1 + 2 * manifest.arity {
+            return Err(invalid_manifest(format!(
+                "arity {} does not match the r1cs, which has {} public inputs and outputs",
+                manifest.arity,
+                r1cs.num_inputs - 1
+            )));
+        }
+        let r1cs = match &manifest.output_order {
+            Some(order) => r1cs
+                .with_output_order(order)
+                .map_err(|e| invalid_manifest(e.to_string()))?,
+            None => r1cs,
+        };
+
+        let declared = InputSignals {
+            signals: manifest.private_inputs.clone(),
+        };
+        let inputs = match &manifest.sym {
+            Some(sym) => {
+                let inputs = InputSignals::from_files(&r1cs_path, &base.join(sym))?;
+                let mut private = inputs.signals.clone();
+                for name in step_input.signal_names() {
+                    private.remove(name);
+                }
+                if private != declared.signals {
+                    return Err(invalid_manifest(format!(
+                        "private_inputs {:?} do not match the input signals of {}: {:?}",
+                        declared.signals,
+                        sym.display(),
+                        private
+                    )));
+                }
+                inputs
+            }
+            None => declared,
+        };
+
+        let pp = match manifest.params_cache.as_ref().map(|p| base.join(p)) {
+            Some(cache) => load_or_create_params(&cache, &r1cs)?,
+            None => create_public_params(r1cs.clone()),
+        };
+
+        Ok(Circuit {
+            manifest,
+            r1cs,
+            pp,
+            step_input,
+            inputs,
+            witness_generator,
+        })
+    }
+
+    pub fn witness_generator(&self) -> FileLocation {
+        FileLocation::PathBuf(self.witness_generator.clone())
+    }
+
+    /// Prove one step per element of `private_inputs`, starting from `z0`.
+    ///
+    /// All private inputs are checked first, against the `.sym` file or the
+    /// manifest's `private_inputs`, and an
+    /// [`InputError`](crate::circom::inputs::InputError) naming the bad step and
+    /// signals is returned as an [`ErrorKind::InvalidInput`] error.
+    pub fn prove(
+        &self,
+        z0: Vec<F1>,
+        private_inputs: Vec<HashMap<String, Value>>,
+    ) -> std::io::Result<RecursiveSNARK<G1, G2, C1, C2>> {
+        if z0.len() != self.manifest.arity {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "z0 has {} elements, expected {}",
+                    z0.len(),
+                    self.manifest.arity
+                ),
+            ));
+        }
+        create_recursive_circuit_with_progress(
+            self.witness_generator(),
+            self.r1cs.clone(),
+            private_inputs,
+            z0,
+            &self.pp,
+            ProofOptions {
+                step_input: self.step_input.clone(),
+                inputs: Some(&self.inputs),
+                ..ProofOptions::default()
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env::current_dir;
+
+    #[test]
+    fn test_load_toy_manifest() {
+        let path = current_dir()
+            .unwrap()
+            .join("examples/toy/toy.manifest.json");
+        let circuit = Circuit::from_manifest(&path).unwrap();
+        assert_eq!(circuit.manifest.arity, 2);
+        assert_eq!(
+            circuit.manifest.private_inputs["adder"],
+            Vec::<usize>::new()
+        );
+        assert_eq!(circuit.r1cs.num_inputs, 5);
+        assert_eq!(circuit.inputs.signals["step_in"], vec![2]);
+    }
+
+    #[test]
+    fn test_params_cache_and_private_inputs() {
+        let toy = current_dir().unwrap().join("examples/toy");
+        let dir = std::env::temp_dir().join(format!("nova_scotia_manifest_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let write_manifest = |private_inputs: Value| {
+            let manifest = serde_json::json!({
+                "r1cs": toy.join("toy.r1cs"),
+                "witness_generator": { "kind": "wasm", "path": toy.join("toy_js/toy.wasm") },
+                "sym": toy.join("toy.sym"),
+                "prime": "vesta",
+                "arity": 2,
+                "private_inputs": private_inputs,
+                "params_cache": "toy.params.json",
+            });
+            let path = dir.join("toy.manifest.json");
+            fs::write(&path, manifest.to_string()).unwrap();
+            path
+        };
+
+        let path = write_manifest(serde_json::json!({ "adder": [2] }));
+        let err = Circuit::from_manifest(&path).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let path = write_manifest(serde_json::json!({ "adder": [] }));
+        let circuit = Circuit::from_manifest(&path).unwrap();
+        let cache_path = dir.join("toy.params.json");
+        let read_cache =
+            || -> Value { serde_json::from_slice(&fs::read(&cache_path).unwrap()).unwrap() };
+        let r1cs_digest = serde_json::to_value(circuit.r1cs.digest()).unwrap();
+        assert_eq!(read_cache()["fingerprint"]["r1cs"], r1cs_digest);
+
+        // a cache written for another circuit is replaced
+        let mut cache = read_cache();
+        cache["fingerprint"]["r1cs"] =
+            serde_json::to_value(crate::digest::Digest([0; 32])).unwrap();
+        fs::write(&cache_path, cache.to_string()).unwrap();
+        Circuit::from_manifest(&path).unwrap();
+        assert_eq!(read_cache()["fingerprint"]["r1cs"], r1cs_digest);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
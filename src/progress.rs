@@ -0,0 +1,223 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::F1;
+
+/// Receives progress events while a recursive proof is being created.
+///
+/// Every method has an empty default implementation, so observers only need to
+/// implement the events they care about. Steps are numbered from zero.
+pub trait ProgressObserver {
+    /// The witness for `step` was generated, taking `elapsed`.
+    fn witness_generated(&mut self, _step: usize, _elapsed: Duration) {}
+
+    /// The witness circom computed for `step`, in circom's order. Signals can be
+    /// read from it by name with a `WitnessView` and the circuit's `.sym` file.
+    fn step_witness(&mut self, _step: usize, _witness: &[F1]) {}
+
+    /// `prove_step` for `step` finished, taking `elapsed`.
+    fn step_proved(&mut self, _step: usize, _elapsed: Duration) {}
+
+    /// The public output `z_i` of `step`, which is the input of the next step.
+    fn step_output(&mut self, _step: usize, _z_i: &[F1]) {}
+}
+
+/// Observer that ignores every event.
+impl ProgressObserver for () {}
+
+/// Shared flag used to stop a proof cleanly between two steps.
+///
+/// Clones share the same flag, so one clone can be handed to a UI or job
+/// scheduler while the other is passed to the prover.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation. The prover stops before starting its next step.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// error returned when a proof is cancelled before `step`
+#[cfg(feature = "prover")]
+pub(crate) fn cancelled_error(step: usize) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::Interrupted,
+        format!("proof cancelled before step {}", step),
+    )
+}
+
+/// `std::time::Instant` is not available on wasm32-unknown-unknown, so time
+/// with the JS clock there instead.
+#[cfg(feature = "prover")]
+pub(crate) struct Stopwatch {
+    #[cfg(not(target_family = "wasm"))]
+    start: std::time::Instant,
+    #[cfg(target_family = "wasm")]
+    start_ms: f64,
+}
+
+#[cfg(feature = "prover")]
+impl Stopwatch {
+    pub(crate) fn start() -> Self {
+        Stopwatch {
+            #[cfg(not(target_family = "wasm"))]
+            start: std::time::Instant::now(),
+            #[cfg(target_family = "wasm")]
+            start_ms: js_sys::Date::now(),
+        }
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        #[cfg(not(target_family = "wasm"))]
+        {
+            self.start.elapsed()
+        }
+        #[cfg(target_family = "wasm")]
+        {
+            Duration::from_secs_f64((js_sys::Date::now() - self.start_ms).max(0.0) / 1000.0)
+        }
+    }
+}
+
+/// Let the worker's event loop run between two steps, e.g. to receive a cancel
+/// message. Awaiting a resolved promise would only run microtasks, so this
+/// waits for a `setTimeout` of zero instead.
+#[cfg(all(feature = "prover", target_family = "wasm"))]
+pub(crate) async fn yield_to_event_loop() {
+    use wasm_bindgen::{JsCast, JsValue};
+
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        let set_timeout = js_sys::Reflect::get(&js_sys::global(), &"setTimeout".into())
+            .ok()
+            .and_then(|f| f.dyn_into::<js_sys::Function>().ok());
+        let _ = match set_timeout {
+            Some(set_timeout) => {
+                set_timeout.call2(&JsValue::UNDEFINED, &resolve, &JsValue::from(0))
+            }
+            None => resolve.call0(&JsValue::UNDEFINED),
+        };
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancellation_token_is_shared() {
+        let token = CancellationToken::new();
+        let handle = token.clone();
+        assert!(!token.is_cancelled());
+        handle.cancel();
+        assert!(token.is_cancelled());
+    }
+
+    /// proofs of the toy circuit, whose events are recorded
+    #[cfg(all(feature = "prover", not(target_family = "wasm")))]
+    mod toy {
+        use super::*;
+        use crate::{
+            circom::reader::load_r1cs, create_public_params,
+            create_recursive_circuit_with_progress, FileLocation, ProofOptions,
+        };
+        use serde_json::json;
+        use std::{collections::HashMap, env::current_dir};
+
+        /// records every event, and cancels `cancellation` once `cancel_after` is
+        /// proved
+        #[derive(Default)]
+        struct Recorder {
+            events: Vec<(&'static str, usize)>,
+            cancel_after: Option<usize>,
+            cancellation: CancellationToken,
+        }
+
+        impl ProgressObserver for Recorder {
+            fn witness_generated(&mut self, step: usize, _elapsed: Duration) {
+                self.events.push(("witness_generated", step));
+            }
+
+            fn step_witness(&mut self, step: usize, _witness: &[F1]) {
+                self.events.push(("step_witness", step));
+            }
+
+            fn step_proved(&mut self, step: usize, _elapsed: Duration) {
+                self.events.push(("step_proved", step));
+            }
+
+            fn step_output(&mut self, step: usize, _z_i: &[F1]) {
+                self.events.push(("step_output", step));
+                if self.cancel_after == Some(step) {
+                    self.cancellation.cancel();
+                }
+            }
+        }
+
+        fn events(steps: usize) -> Vec<(&'static str, usize)> {
+            (0..steps)
+                .flat_map(|step| {
+                    [
+                        ("witness_generated", step),
+                        ("step_witness", step),
+                        ("step_proved", step),
+                        ("step_output", step),
+                    ]
+                })
+                .collect()
+        }
+
+        #[test]
+        fn test_progress_events_and_cancellation() {
+            let root = current_dir().unwrap().join("examples/toy");
+            let r1cs = load_r1cs(&FileLocation::PathBuf(root.join("toy.r1cs")));
+            let pp = create_public_params(r1cs.clone());
+            let private_inputs: Vec<HashMap<String, serde_json::Value>> = (0..3)
+                .map(|i| HashMap::from([("adder".to_string(), json!(i))]))
+                .collect();
+            let prove = |observer: &mut Recorder| {
+                let cancellation = observer.cancellation.clone();
+                create_recursive_circuit_with_progress(
+                    FileLocation::PathBuf(root.join("toy_js/toy.wasm")),
+                    r1cs.clone(),
+                    private_inputs.clone(),
+                    vec![F1::from(10), F1::from(10)],
+                    &pp,
+                    ProofOptions {
+                        observer: Some(observer),
+                        cancellation,
+                        ..ProofOptions::default()
+                    },
+                )
+            };
+
+            let mut observer = Recorder::default();
+            prove(&mut observer).unwrap();
+            assert_eq!(observer.events, events(3));
+
+            // cancelled between the first and second step
+            let mut observer = Recorder {
+                cancel_after: Some(0),
+                ..Recorder::default()
+            };
+            let error = prove(&mut observer).err().unwrap();
+            assert_eq!(error.kind(), std::io::ErrorKind::Interrupted);
+            assert_eq!(observer.events, events(1));
+        }
+    }
+}
@@ -0,0 +1,411 @@
+//! Local proving service: an HTTP API in front of a queue of proving jobs.
+//!
+//! Circuits are registered once at startup, and their `PublicParams` are
+//! created then and shared by every job. Jobs are proved by a fixed number of
+//! worker tasks and compressed, and their status and proof can be polled:
+//!
+//! - `GET /circuits` lists the registered circuit ids
+//! - `POST /jobs` with `{"circuit": id, "z0": [...], "private_inputs": [...]}`
+//!   queues a job and returns its `id`
+//! - `GET /jobs/:id` returns its `status`: `queued`, `running`, `done` or `failed`
+//! - `GET /jobs/:id/proof` returns the serialized `CompressedSNARK` once done
+//!
+//! `z0` holds decimal strings, `private_inputs` one object of circom inputs per
+//! step, as for [`create_recursive_circuit`](crate::create_recursive_circuit).
+//! Jobs are checked when they are submitted, and a bad one is rejected with
+//! `400 Bad Request`. At most `queue_capacity` jobs wait for a worker; more are
+//! rejected with `503 Service Unavailable` until the queue drains. Finished jobs
+//! are kept in memory until the service stops.
+use std::{
+    collections::HashMap,
+    io::Error,
+    net::TcpListener,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use ff::PrimeField;
+use nova_snark::PublicParams;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+
+use crate::{
+    circom::{circuit::R1CS, inputs::InputSignals},
+    compress_recursive_snark, create_public_params, create_recursive_circuit_async,
+    step_input::StepInputEncoding,
+    FileLocation, ProofOptions, C1, C2, F1, G1, G2,
+};
+
+/// A circuit the service can prove, with its preloaded public parameters.
+pub struct RegisteredCircuit {
+    pub r1cs: R1CS<F1>,
+    /// C++ witness generator binary or circom wasm file
+    pub witness_generator: PathBuf,
+    pub pp: Arc<PublicParams<G1, G2, C1, C2>>,
+    /// input signals from the circuit's `.sym` file, checked when a job is
+    /// submitted
+    pub inputs: Option<InputSignals>,
+}
+
+impl RegisteredCircuit {
+    /// Create the public parameters for `r1cs`, which may take a while for
+    /// large circuits.
+    pub fn new(r1cs: R1CS<F1>, witness_generator: PathBuf) -> Self {
+        let pp = Arc::new(create_public_params(r1cs.clone()));
+        RegisteredCircuit {
+            r1cs,
+            witness_generator,
+            pp,
+            inputs: None,
+        }
+    }
+
+    /// Reject jobs whose private inputs don't match `inputs`.
+    pub fn with_inputs(mut self, inputs: InputSignals) -> Self {
+        self.inputs = Some(inputs);
+        self
+    }
+}
+
+#[derive(Deserialize)]
+struct JobRequest {
+    circuit: String,
+    z0: Vec<String>,
+    private_inputs: Vec<HashMap<String, Value>>,
+}
+
+struct Job {
+    id: u64,
+    circuit: Arc<RegisteredCircuit>,
+    z0: Vec<F1>,
+    private_inputs: Vec<HashMap<String, Value>>,
+}
+
+enum JobStatus {
+    Queued,
+    Running,
+    Done(String),
+    Failed(String),
+}
+
+impl JobStatus {
+    fn name(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Done(_) => "done",
+            JobStatus::Failed(_) => "failed",
+        }
+    }
+}
+
+struct Inner {
+    circuits: HashMap<String, Arc<RegisteredCircuit>>,
+    jobs: Mutex<HashMap<u64, JobStatus>>,
+    next_id: AtomicU64,
+    queue: mpsc::Sender<Job>,
+}
+
+/// Handle to a running proving service. Clones share the same queue.
+#[derive(Clone)]
+pub struct ProvingService {
+    inner: Arc<Inner>,
+}
+
+impl ProvingService {
+    /// Start `workers` proving tasks for `circuits`, keyed by circuit id, with
+    /// room for `queue_capacity` jobs waiting for them. Must be called from
+    /// within a tokio runtime.
+    pub fn new(
+        circuits: HashMap<String, RegisteredCircuit>,
+        workers: usize,
+        queue_capacity: usize,
+    ) -> Self {
+        let (queue, receiver) = mpsc::channel(queue_capacity.max(1));
+        let service = ProvingService {
+            inner: Arc::new(Inner {
+                circuits: circuits
+                    .into_iter()
+                    .map(|(id, circuit)| (id, Arc::new(circuit)))
+                    .collect(),
+                jobs: Mutex::new(HashMap::new()),
+                next_id: AtomicU64::new(0),
+                queue,
+            }),
+        };
+
+        let receiver = Arc::new(tokio::sync::Mutex::new(receiver));
+        for _ in 0..workers.max(1) {
+            let service = service.clone();
+            let receiver = receiver.clone();
+            tokio::spawn(async move {
+                loop {
+                    let job = receiver.lock().await.recv().await;
+                    match job {
+                        Some(job) => service.run(job).await,
+                        None => break,
+                    }
+                }
+            });
+        }
+        service
+    }
+
+    fn set_status(&self, id: u64, status: JobStatus) {
+        self.inner.jobs.lock().unwrap().insert(id, status);
+    }
+
+    #[tracing::instrument(skip_all, name = "job", fields(id = job.id))]
+    async fn run(&self, job: Job) {
+        self.set_status(job.id, JobStatus::Running);
+        let status = match prove(job.circuit, job.private_inputs, job.z0).await {
+            Ok(proof) => JobStatus::Done(proof),
+            Err(e) => {
+                tracing::warn!("job failed: {}", e);
+                JobStatus::Failed(e.to_string())
+            }
+        };
+        self.set_status(job.id, status);
+    }
+
+    fn submit(&self, request: JobRequest) -> Result<u64, (StatusCode, String)> {
+        let bad_request = |message: String| (StatusCode::BAD_REQUEST, message);
+        let circuit = self
+            .inner
+            .circuits
+            .get(&request.circuit)
+            .ok_or_else(|| bad_request(format!("unknown circuit {}", request.circuit)))?
+            .clone();
+        let z0 = request
+            .z0
+            .iter()
+            .map(|x| {
+                F1::from_str_vartime(x)
+                    .ok_or_else(|| bad_request(format!("invalid z0 element {}", x)))
+            })
+            .collect::<Result<Vec<F1>, _>>()?;
+        let arity = (circuit.r1cs.num_inputs - 1) / 2;
+        if z0.len() != arity {
+            return Err(bad_request(format!(
+                "z0 has {} elements, expected {}",
+                z0.len(),
+                arity
+            )));
+        }
+        if request.private_inputs.is_empty() {
+            return Err(bad_request("at least one step is required".to_string()));
+        }
+        if let Some(inputs) = &circuit.inputs {
+            inputs
+                .check_all(&request.private_inputs, &StepInputEncoding::default())
+                .map_err(|e| bad_request(e.to_string()))?;
+        }
+
+        let permit = self.inner.queue.try_reserve().map_err(|e| {
+            let message = match e {
+                mpsc::error::TrySendError::Full(()) => "too many pending jobs, retry later",
+                mpsc::error::TrySendError::Closed(()) => "service is shutting down",
+            };
+            (StatusCode::SERVICE_UNAVAILABLE, message.to_string())
+        })?;
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        self.set_status(id, JobStatus::Queued);
+        permit.send(Job {
+            id,
+            circuit,
+            z0,
+            private_inputs: request.private_inputs,
+        });
+        Ok(id)
+    }
+
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/circuits", get(list_circuits))
+            .route("/jobs", post(submit_job))
+            .route("/jobs/:id", get(job_status))
+            .route("/jobs/:id/proof", get(job_proof))
+            .with_state(self)
+    }
+
+    /// Serve the HTTP API on `listener` until the future is dropped.
+    pub async fn serve(self, listener: TcpListener) -> std::io::Result<()> {
+        axum::Server::from_tcp(listener)
+            .map_err(Error::other)?
+            .serve(self.router().into_make_service())
+            .await
+            .map_err(Error::other)
+    }
+}
+
+async fn prove(
+    circuit: Arc<RegisteredCircuit>,
+    private_inputs: Vec<HashMap<String, Value>>,
+    z0: Vec<F1>,
+) -> std::io::Result<String> {
+    let recursive_snark = create_recursive_circuit_async(
+        FileLocation::PathBuf(circuit.witness_generator.clone()),
+        circuit.r1cs.clone(),
+        private_inputs,
+        z0,
+        circuit.pp.clone(),
+        ProofOptions::default(),
+    )
+    .await?;
+
+    let pp = circuit.pp.clone();
+    let compressed_snark =
+        tokio::task::spawn_blocking(move || compress_recursive_snark(&pp, &recursive_snark))
+            .await
+            .map_err(Error::other)?
+            .map_err(|e| Error::other(format!("compression failed: {:?}", e)))?;
+    Ok(serde_json::to_string(&compressed_snark)?)
+}
+
+fn error(status: StatusCode, message: impl ToString) -> Response {
+    (status, Json(json!({ "error": message.to_string() }))).into_response()
+}
+
+async fn list_circuits(State(service): State<ProvingService>) -> Json<Vec<String>> {
+    let mut ids: Vec<String> = service.inner.circuits.keys().cloned().collect();
+    ids.sort();
+    Json(ids)
+}
+
+async fn submit_job(
+    State(service): State<ProvingService>,
+    Json(request): Json<JobRequest>,
+) -> Response {
+    match service.submit(request) {
+        Ok(id) => (StatusCode::ACCEPTED, Json(json!({ "id": id }))).into_response(),
+        Err((status, e)) => error(status, e),
+    }
+}
+
+async fn job_status(State(service): State<ProvingService>, Path(id): Path<u64>) -> Response {
+    let jobs = service.inner.jobs.lock().unwrap();
+    match jobs.get(&id) {
+        Some(JobStatus::Failed(e)) => {
+            Json(json!({ "id": id, "status": "failed", "error": e })).into_response()
+        }
+        Some(status) => Json(json!({ "id": id, "status": status.name() })).into_response(),
+        None => error(StatusCode::NOT_FOUND, format!("unknown job {}", id)),
+    }
+}
+
+async fn job_proof(State(service): State<ProvingService>, Path(id): Path<u64>) -> Response {
+    let jobs = service.inner.jobs.lock().unwrap();
+    match jobs.get(&id) {
+        Some(JobStatus::Done(proof)) => {
+            ([(header::CONTENT_TYPE, "application/json")], proof.clone()).into_response()
+        }
+        Some(status) => error(
+            StatusCode::CONFLICT,
+            format!("job {} is {}", id, status.name()),
+        ),
+        None => error(StatusCode::NOT_FOUND, format!("unknown job {}", id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circom::reader::load_r1cs;
+    use std::{env::current_dir, net::SocketAddr, time::Duration};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    };
+
+    async fn start(circuits: HashMap<String, RegisteredCircuit>) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(ProvingService::new(circuits, 1, 1).serve(listener));
+        addr
+    }
+
+    async fn request(addr: SocketAddr, method: &str, path: &str, body: &str) -> (u16, Value) {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request = format!(
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = response.split_once("\r\n\r\n").unwrap().1;
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_prove_toy_job() {
+        let root = current_dir().unwrap().join("examples/toy");
+        let r1cs = load_r1cs(&FileLocation::PathBuf(root.join("toy.r1cs")));
+        // the committed C++ witness generator is a macOS binary, node runs anywhere
+        let inputs =
+            InputSignals::from_files(&root.join("toy.r1cs"), &root.join("toy.sym")).unwrap();
+        let circuit =
+            RegisteredCircuit::new(r1cs, root.join("toy_js/toy.wasm")).with_inputs(inputs);
+        let addr = start(HashMap::from([("toy".to_string(), circuit)])).await;
+
+        let (status, body) = request(addr, "GET", "/jobs/0", "").await;
+        assert_eq!(status, 404, "{}", body);
+        let job =
+            json!({ "circuit": "missing", "z0": ["10", "10"], "private_inputs": [{ "adder": 0 }] });
+        let (status, _) = request(addr, "POST", "/jobs", &job.to_string()).await;
+        assert_eq!(status, 400);
+        let job =
+            json!({ "circuit": "toy", "z0": ["10", "10"], "private_inputs": [{ "addr": 0 }] });
+        let (status, body) = request(addr, "POST", "/jobs", &job.to_string()).await;
+        assert_eq!(status, 400);
+        assert!(body["error"].as_str().unwrap().contains("addr"), "{}", body);
+        let job = json!({ "circuit": "toy", "z0": ["10"], "private_inputs": [{ "adder": 0 }] });
+        let (status, body) = request(addr, "POST", "/jobs", &job.to_string()).await;
+        assert_eq!(status, 400);
+        assert!(body["error"].as_str().unwrap().contains("z0"), "{}", body);
+
+        let job = json!({
+            "circuit": "toy",
+            "z0": ["10", "10"],
+            "private_inputs": [{ "adder": 0 }, { "adder": 1 }],
+        });
+        let (status, body) = request(addr, "POST", "/jobs", &job.to_string()).await;
+        assert_eq!(status, 202);
+        let path = format!("/jobs/{}", body["id"]);
+
+        // once the only worker runs it, one more job fits in the queue
+        while request(addr, "GET", &path, "").await.1["status"] == "queued" {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let (status, _) = request(addr, "POST", "/jobs", &job.to_string()).await;
+        assert_eq!(status, 202);
+        let (status, body) = request(addr, "POST", "/jobs", &job.to_string()).await;
+        assert_eq!(status, 503, "{}", body);
+
+        loop {
+            let (_, body) = request(addr, "GET", &path, "").await;
+            match body["status"].as_str().unwrap() {
+                "done" => break,
+                "failed" => panic!("job failed: {}", body["error"]),
+                _ => tokio::time::sleep(Duration::from_millis(100)).await,
+            }
+        }
+        let (status, _) = request(addr, "GET", &format!("{}/proof", path), "").await;
+        assert_eq!(status, 200);
+    }
+}
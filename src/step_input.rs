@@ -0,0 +1,255 @@
+//! How the folded state `z_i` is passed to the circom witness generator.
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind},
+};
+
+#[cfg(any(feature = "ffi", target_family = "wasm"))]
+use ff::PrimeField;
+use serde::{Deserialize, Serialize};
+#[cfg(not(target_family = "wasm"))]
+use serde_json::Map;
+use serde_json::Value;
+
+#[cfg(not(target_family = "wasm"))]
+use crate::to_decimal_string;
+use crate::F1;
+
+/// One signal receiving `size` consecutive elements of `z_i`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepSignal {
+    pub name: String,
+    /// a size of 1 is passed as a single value, which circom also accepts for
+    /// an array of size 1
+    pub size: usize,
+}
+
+/// Input signals holding `z_i`.
+///
+/// Circom orders public inputs as they are listed in `component main { public
+/// [...] }`, and `z_i` must follow that order.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StepInputEncoding {
+    /// all of `z_i` in one array signal
+    Array(String),
+    /// `z_i` split across several signals, in order, e.g. `{"root": z[0], "counter": z[1]}`
+    Signals(Vec<StepSignal>),
+}
+
+impl Default for StepInputEncoding {
+    fn default() -> Self {
+        StepInputEncoding::Array("step_in".to_string())
+    }
+}
+
+impl StepInputEncoding {
+    pub fn signal_names(&self) -> Vec<&str> {
+        match self {
+            StepInputEncoding::Array(name) => vec![name.as_str()],
+            StepInputEncoding::Signals(signals) => {
+                signals.iter().map(|s| s.name.as_str()).collect()
+            }
+        }
+    }
+
+    /// Check that the signals take exactly `arity` elements.
+    pub fn check_arity(&self, arity: usize) -> std::io::Result<()> {
+        match self {
+            StepInputEncoding::Array(_) => Ok(()),
+            StepInputEncoding::Signals(signals) => {
+                let size: usize = signals.iter().map(|s| s.size).sum();
+                if size == arity {
+                    Ok(())
+                } else {
+                    Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "step input signals take {} elements, but z_i has {}",
+                            size, arity
+                        ),
+                    ))
+                }
+            }
+        }
+    }
+
+    /// `z` split into the step signals
+    fn split<'a>(&'a self, z: &'a [F1]) -> Vec<(&'a str, &'a [F1])> {
+        match self {
+            StepInputEncoding::Array(name) => vec![(name.as_str(), z)],
+            StepInputEncoding::Signals(signals) => {
+                let mut offset = 0;
+                signals
+                    .iter()
+                    .map(|signal| {
+                        let values = &z[offset..offset + signal.size];
+                        offset += signal.size;
+                        (signal.name.as_str(), values)
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// the step signals of `z`, as circom decimal strings
+    #[cfg(not(target_family = "wasm"))]
+    fn encode(&self, z: &[F1], input: &mut Map<String, Value>) {
+        for (name, values) in self.split(z) {
+            let value = match (self, values) {
+                (StepInputEncoding::Signals(_), [x]) => Value::String(to_decimal_string(x)),
+                _ => Value::Array(
+                    values
+                        .iter()
+                        .map(|x| Value::String(to_decimal_string(x)))
+                        .collect(),
+                ),
+            };
+            input.insert(name.to_string(), value);
+        }
+    }
+}
+
+/// build the witness generator input json for one step of recursion
+#[cfg(not(target_family = "wasm"))]
+pub(crate) fn circom_input_json(
+    encoding: &StepInputEncoding,
+    step_in: &[F1],
+    private_input: &HashMap<String, Value>,
+) -> String {
+    let mut input: Map<String, Value> = private_input
+        .iter()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    encoding.encode(step_in, &mut input);
+    serde_json::to_string(&input).unwrap()
+}
+
+/// the elements of a circom input value, flattening arrays like circom does
+#[cfg(any(feature = "ffi", target_family = "wasm"))]
+fn flatten_input<Fr: PrimeField>(
+    name: &str,
+    value: &Value,
+    elements: &mut Vec<Fr>,
+) -> std::io::Result<()> {
+    let element = |s: &str| match s.strip_prefix('-') {
+        Some(abs) => Fr::from_str_vartime(abs).map(|x| -x),
+        None => Fr::from_str_vartime(s),
+    };
+    let parsed = match value {
+        Value::Array(values) => {
+            for value in values {
+                flatten_input(name, value, elements)?;
+            }
+            return Ok(());
+        }
+        Value::Number(n) => element(&n.to_string()),
+        Value::String(s) => element(s),
+        _ => None,
+    };
+    match parsed {
+        Some(x) => {
+            elements.push(x);
+            Ok(())
+        }
+        None => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "input signal {} is not a decimal field element: {}",
+                name, value
+            ),
+        )),
+    }
+}
+
+/// circom input signals as field elements
+#[cfg(any(feature = "ffi", target_family = "wasm"))]
+pub(crate) fn input_signals<Fr: PrimeField>(
+    input: &HashMap<String, Value>,
+) -> std::io::Result<Vec<(String, Vec<Fr>)>> {
+    let mut signals = vec![];
+    for (name, value) in input {
+        let mut elements = vec![];
+        flatten_input(name, value, &mut elements)?;
+        signals.push((name.clone(), elements));
+    }
+    Ok(signals)
+}
+
+/// the input signals for one step of recursion as field elements, for witness
+/// generators called in process
+#[cfg(any(feature = "ffi", target_family = "wasm"))]
+pub(crate) fn circom_input_signals(
+    encoding: &StepInputEncoding,
+    step_in: &[F1],
+    private_input: &HashMap<String, Value>,
+) -> std::io::Result<Vec<(String, Vec<F1>)>> {
+    let mut signals = input_signals(private_input)?;
+    for (name, values) in encoding.split(step_in) {
+        signals.push((name.to_string(), values.to_vec()));
+    }
+    Ok(signals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_split_step_input() {
+        let encoding = StepInputEncoding::Signals(vec![
+            StepSignal {
+                name: "root".to_string(),
+                size: 1,
+            },
+            StepSignal {
+                name: "path".to_string(),
+                size: 2,
+            },
+        ]);
+        assert!(encoding.check_arity(3).is_ok());
+        assert!(encoding.check_arity(2).is_err());
+
+        let private_input = HashMap::from([("adder".to_string(), json!(1))]);
+        let z = [F1::from(7), F1::from(8), F1::from(9)];
+        let input: Value =
+            serde_json::from_str(&circom_input_json(&encoding, &z, &private_input)).unwrap();
+        assert_eq!(
+            input,
+            json!({ "adder": 1, "root": "7", "path": ["8", "9"] })
+        );
+
+        let input: Value = serde_json::from_str(&circom_input_json(
+            &StepInputEncoding::default(),
+            &z[..2],
+            &private_input,
+        ))
+        .unwrap();
+        assert_eq!(input, json!({ "adder": 1, "step_in": ["7", "8"] }));
+    }
+
+    #[cfg(feature = "ffi")]
+    #[test]
+    fn test_input_signals() {
+        let private_input = HashMap::from([("m".to_string(), json!([[1, "2"], [-1, "3"]]))]);
+        let signals = circom_input_signals(
+            &StepInputEncoding::default(),
+            &[F1::from(7)],
+            &private_input,
+        )
+        .unwrap();
+        assert_eq!(
+            signals,
+            vec![
+                (
+                    "m".to_string(),
+                    vec![F1::from(1), F1::from(2), -F1::from(1), F1::from(3)]
+                ),
+                ("step_in".to_string(), vec![F1::from(7)]),
+            ]
+        );
+
+        let private_input = HashMap::from([("m".to_string(), json!("0x10"))]);
+        assert!(circom_input_signals(&StepInputEncoding::default(), &[], &private_input).is_err());
+    }
+}
@@ -0,0 +1,208 @@
+use std::fmt;
+
+use nova_snark::{errors::NovaError, traits::Group, CompressedSNARK, PublicParams, RecursiveSNARK};
+
+use crate::{
+    digest::{CircuitFingerprint, FingerprintedParams, FingerprintedProof},
+    to_decimal_string, C1, C2, F1, G1, G2, S1, S2,
+};
+
+/// Error returned by the verify helpers.
+#[derive(Debug)]
+pub enum VerifyError {
+    /// The proof itself did not verify.
+    Nova(Box<NovaError>),
+    /// The proof verified, but its final step output is not the expected `z_n`.
+    UnexpectedOutput {
+        expected: Vec<String>,
+        actual: Vec<String>,
+    },
+    /// The proof was made for another circuit or other public parameters.
+    FingerprintMismatch {
+        expected: Box<CircuitFingerprint>,
+        actual: Box<CircuitFingerprint>,
+    },
+    /// The verifier key or the proof could not be deserialized.
+    Deserialize(serde_json::Error),
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::Nova(e) => write!(f, "proof verification failed: {:?}", e),
+            VerifyError::UnexpectedOutput { expected, actual } => write!(
+                f,
+                "unexpected final step output: expected {:?}, got {:?}",
+                expected, actual
+            ),
+            VerifyError::FingerprintMismatch { expected, actual } => write!(
+                f,
+                "proof is for another circuit: expected r1cs {} and params {}, got r1cs {} and params {}",
+                expected.r1cs, expected.params, actual.r1cs, actual.params
+            ),
+            VerifyError::Deserialize(e) => write!(f, "invalid verifier key or proof: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+impl From<NovaError> for VerifyError {
+    fn from(e: NovaError) -> Self {
+        VerifyError::Nova(Box::new(e))
+    }
+}
+
+/// circom-style decimal strings of the step outputs, e.g. for JSON or logging
+pub fn to_decimal_strings(z: &[F1]) -> Vec<String> {
+    z.iter().map(to_decimal_string).collect()
+}
+
+fn z0_secondary() -> Vec<<G2 as Group>::Scalar> {
+    vec![<G2 as Group>::Scalar::zero()]
+}
+
+fn check_expected(z_n: Vec<F1>, expected_z_n: Option<&[F1]>) -> Result<Vec<F1>, VerifyError> {
+    match expected_z_n {
+        Some(expected) if expected != z_n.as_slice() => Err(VerifyError::UnexpectedOutput {
+            expected: to_decimal_strings(expected),
+            actual: to_decimal_strings(&z_n),
+        }),
+        _ => Ok(z_n),
+    }
+}
+
+/// Verify a recursive SNARK of `num_steps` steps starting from `z0`, and return
+/// the final step output `z_n`.
+///
+/// The secondary circuit's input is filled in the same way as when proving.
+pub fn verify_recursive_proof(
+    pp: &PublicParams<G1, G2, C1, C2>,
+    proof: &RecursiveSNARK<G1, G2, C1, C2>,
+    num_steps: usize,
+    z0: Vec<F1>,
+) -> Result<Vec<F1>, VerifyError> {
+    verify_recursive_proof_with_expected(pp, proof, num_steps, z0, None)
+}
+
+/// Same as [`verify_recursive_proof`], additionally failing with
+/// [`VerifyError::UnexpectedOutput`] if `z_n` differs from `expected_z_n`.
+pub fn verify_recursive_proof_with_expected(
+    pp: &PublicParams<G1, G2, C1, C2>,
+    proof: &RecursiveSNARK<G1, G2, C1, C2>,
+    num_steps: usize,
+    z0: Vec<F1>,
+    expected_z_n: Option<&[F1]>,
+) -> Result<Vec<F1>, VerifyError> {
+    let (z_n, _) = proof.verify(pp, num_steps, z0, z0_secondary())?;
+    check_expected(z_n, expected_z_n)
+}
+
+/// Verify a compressed SNARK of `num_steps` steps starting from `z0`, and return
+/// the final step output `z_n`.
+pub fn verify_compressed_proof(
+    pp: &PublicParams<G1, G2, C1, C2>,
+    proof: &CompressedSNARK<G1, G2, C1, C2, S1, S2>,
+    num_steps: usize,
+    z0: Vec<F1>,
+) -> Result<Vec<F1>, VerifyError> {
+    verify_compressed_proof_with_expected(pp, proof, num_steps, z0, None)
+}
+
+/// Same as [`verify_compressed_proof`], additionally failing with
+/// [`VerifyError::UnexpectedOutput`] if `z_n` differs from `expected_z_n`.
+pub fn verify_compressed_proof_with_expected(
+    pp: &PublicParams<G1, G2, C1, C2>,
+    proof: &CompressedSNARK<G1, G2, C1, C2, S1, S2>,
+    num_steps: usize,
+    z0: Vec<F1>,
+    expected_z_n: Option<&[F1]>,
+) -> Result<Vec<F1>, VerifyError> {
+    let (z_n, _) = proof.verify(pp, num_steps, z0, z0_secondary())?;
+    check_expected(z_n, expected_z_n)
+}
+
+/// Deserialize a verifier key and a compressed SNARK, both serialized with
+/// serde_json, and verify the proof as [`verify_compressed_proof`] does.
+///
+/// With nova-snark 0.15 the verifier key is the serialized public parameters.
+/// Only this module is needed to verify, so it also works in a build without
+/// the `prover` feature.
+pub fn verify_compressed_proof_from_bytes(
+    verifier_key: &[u8],
+    proof: &[u8],
+    num_steps: usize,
+    z0: Vec<F1>,
+) -> Result<Vec<F1>, VerifyError> {
+    let pp: PublicParams<G1, G2, C1, C2> =
+        serde_json::from_slice(verifier_key).map_err(VerifyError::Deserialize)?;
+    let proof: CompressedSNARK<G1, G2, C1, C2, S1, S2> =
+        serde_json::from_slice(proof).map_err(VerifyError::Deserialize)?;
+    verify_compressed_proof(&pp, &proof, num_steps, z0)
+}
+
+fn check_fingerprint(
+    expected: &CircuitFingerprint,
+    actual: &CircuitFingerprint,
+) -> Result<(), VerifyError> {
+    if expected != actual {
+        return Err(VerifyError::FingerprintMismatch {
+            expected: Box::new(*expected),
+            actual: Box::new(*actual),
+        });
+    }
+    Ok(())
+}
+
+/// Same as [`verify_recursive_proof`] for a proof carrying a fingerprint, which
+/// is compared against the fingerprint of `params` before running the expensive
+/// check.
+pub fn verify_fingerprinted_recursive_proof(
+    params: &FingerprintedParams,
+    proof: &FingerprintedProof<RecursiveSNARK<G1, G2, C1, C2>>,
+    num_steps: usize,
+    z0: Vec<F1>,
+) -> Result<Vec<F1>, VerifyError> {
+    check_fingerprint(params.fingerprint(), &proof.fingerprint)?;
+    verify_recursive_proof(params.pp(), &proof.proof, num_steps, z0)
+}
+
+/// Same as [`verify_compressed_proof`] for a proof carrying a fingerprint, which
+/// is compared against the fingerprint of `params` before running the expensive
+/// check.
+pub fn verify_fingerprinted_compressed_proof(
+    params: &FingerprintedParams,
+    proof: &FingerprintedProof<CompressedSNARK<G1, G2, C1, C2, S1, S2>>,
+    num_steps: usize,
+    z0: Vec<F1>,
+) -> Result<Vec<F1>, VerifyError> {
+    check_fingerprint(params.fingerprint(), &proof.fingerprint)?;
+    verify_compressed_proof(params.pp(), &proof.proof, num_steps, z0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expected_output_mismatch() {
+        let z_n = vec![F1::from(20), F1::from(21)];
+        assert!(check_expected(z_n.clone(), None).is_ok());
+        assert!(check_expected(z_n.clone(), Some(&z_n)).is_ok());
+
+        let err = check_expected(z_n, Some(&[F1::from(20), F1::from(22)])).unwrap_err();
+        match err {
+            VerifyError::UnexpectedOutput { expected, actual } => {
+                assert_eq!(expected, vec!["20", "22"]);
+                assert_eq!(actual, vec!["20", "21"]);
+            }
+            _ => panic!("expected UnexpectedOutput"),
+        }
+    }
+
+    #[test]
+    fn test_invalid_verifier_key() {
+        let res = verify_compressed_proof_from_bytes(b"{}", b"{}", 1, vec![F1::from(0)]);
+        assert!(matches!(res, Err(VerifyError::Deserialize(_))));
+    }
+}
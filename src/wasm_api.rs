@@ -0,0 +1,335 @@
+//! Reusable `#[wasm_bindgen]` API, enabled with the `wasm` feature.
+//!
+//! Public parameters, proofs and step inputs cross the JS boundary as JSON
+//! strings, circuit artifacts as `Uint8Array`s. Failures are thrown as
+//! `NovaScotiaError` instances whose `kind` tells callers what went wrong.
+//!
+//! Proving blocks the calling thread, so run it in a dedicated worker after
+//! `initThreadPool`. [`prove_with_progress`] reports every step back to the
+//! worker, which can forward it to the page with `postMessage`.
+use std::{collections::HashMap, io::ErrorKind, time::Duration};
+
+use ff::PrimeField;
+use js_sys::{Array, Function, Object, Reflect};
+use nova_snark::{CompressedSNARK, PublicParams, RecursiveSNARK};
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+
+pub use wasm_bindgen_rayon::init_thread_pool;
+
+use crate::{
+    circom::{
+        circuit::R1CS, file::ParseLimits, inputs::InputSignals, reader::try_load_r1cs_from_bytes,
+    },
+    compress_recursive_snark, create_public_params, create_recursive_circuit_with_progress,
+    progress::{CancellationToken, ProgressObserver},
+    public_params_from_bytes,
+    verify::{to_decimal_strings, verify_compressed_proof},
+    FileLocation, ProofOptions, ProveStepError, C1, C2, F1, G1, G2, S1, S2,
+};
+
+/// Error thrown to JS by the functions of this module.
+#[wasm_bindgen(js_name = NovaScotiaError)]
+#[derive(Debug)]
+pub struct WasmError {
+    kind: String,
+    message: String,
+}
+
+#[wasm_bindgen(js_class = NovaScotiaError)]
+impl WasmError {
+    /// One of `InvalidInput`, `WitnessGeneration`, `Proving`, `Cancelled` or
+    /// `Verification`.
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> String {
+        self.kind.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_string(&self) -> String {
+        format!("{}: {}", self.kind, self.message)
+    }
+}
+
+impl WasmError {
+    fn new(kind: &str, message: impl ToString) -> Self {
+        WasmError {
+            kind: kind.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    fn invalid_input(message: impl ToString) -> Self {
+        Self::new("InvalidInput", message)
+    }
+
+    fn from_proving(error: std::io::Error) -> Self {
+        if error
+            .get_ref()
+            .map_or(false, |inner| inner.is::<ProveStepError>())
+        {
+            return Self::new("Proving", error);
+        }
+        match error.kind() {
+            ErrorKind::Interrupted => Self::new("Cancelled", error),
+            // inputs rejected before or by the witness generator, or a witness
+            // generator that doesn't match the circuit
+            ErrorKind::InvalidInput | ErrorKind::InvalidData => Self::new("InvalidInput", error),
+            _ => Self::new("WitnessGeneration", error),
+        }
+    }
+}
+
+type Params = PublicParams<G1, G2, C1, C2>;
+
+fn parse_params(params: &str) -> Result<Params, WasmError> {
+    public_params_from_bytes(params.as_bytes())
+        .map_err(|e| WasmError::invalid_input(format!("invalid public params: {}", e)))
+}
+
+/// parse a JSON array of field elements, given as decimal strings or numbers
+fn parse_field_elements(json: &str) -> Result<Vec<F1>, WasmError> {
+    let values: Vec<Value> = serde_json::from_str(json)
+        .map_err(|e| WasmError::invalid_input(format!("expected a JSON array: {}", e)))?;
+    values
+        .iter()
+        .map(|value| {
+            let decimal = match value {
+                Value::String(s) => s.clone(),
+                Value::Number(n) => n.to_string(),
+                _ => {
+                    return Err(WasmError::invalid_input(format!(
+                        "not a field element: {}",
+                        value
+                    )))
+                }
+            };
+            Option::from(F1::from_str_vartime(&decimal))
+                .ok_or_else(|| WasmError::invalid_input(format!("not a field element: {}", value)))
+        })
+        .collect()
+}
+
+fn parse_r1cs(r1cs: &[u8]) -> Result<R1CS<F1>, WasmError> {
+    try_load_r1cs_from_bytes(r1cs, &ParseLimits::default()).map_err(WasmError::invalid_input)
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String, WasmError> {
+    serde_json::to_string(value).map_err(|e| WasmError::new("InvalidInput", e))
+}
+
+/// Create public parameters for the circuit in `r1cs` (binary or JSON r1cs file).
+/// Returns the serialized parameters.
+#[wasm_bindgen]
+pub fn setup(r1cs: &[u8]) -> Result<String, WasmError> {
+    to_json(&create_public_params(parse_r1cs(r1cs)?))
+}
+
+/// Prove `private_inputs.length` steps of the circuit, starting from `z0`.
+///
+/// `z0` is a JSON array of decimal strings and `private_inputs` a JSON array with
+/// one object of circom inputs per step. Returns the serialized `RecursiveSNARK`.
+///
+/// When the contents of the circuit's `.sym` file are passed as `sym`, every
+/// step's input is checked against the circuit's input signals first, and a bad
+/// one is thrown as an `InvalidInput` error.
+#[wasm_bindgen]
+pub async fn prove(
+    params: &str,
+    r1cs: &[u8],
+    witness_generator_wasm: &[u8],
+    z0: &str,
+    private_inputs: &str,
+    sym: Option<String>,
+) -> Result<String, WasmError> {
+    let (pp, z0, private_inputs) = parse_prove_inputs(params, z0, private_inputs)?;
+    let inputs = parse_input_signals(r1cs, sym)?;
+    let recursive_snark = create_recursive_circuit_with_progress(
+        FileLocation::Bytes(witness_generator_wasm.to_vec()),
+        parse_r1cs(r1cs)?,
+        private_inputs,
+        z0,
+        &pp,
+        ProofOptions {
+            inputs: inputs.as_ref(),
+            ..ProofOptions::default()
+        },
+    )
+    .await
+    .map_err(WasmError::from_proving)?;
+    to_json(&recursive_snark)
+}
+
+/// Handle to stop a running [`prove_with_progress`] between two steps.
+///
+/// A worker keeps it next to its message handler and calls `cancel` when the
+/// page asks to stop. The prover yields to the worker's event loop before every
+/// step, so the message is handled there and proving stops with a `Cancelled`
+/// error.
+#[wasm_bindgen]
+#[derive(Clone, Default)]
+pub struct ProofCancellation(CancellationToken);
+
+#[wasm_bindgen]
+impl ProofCancellation {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.cancel();
+    }
+
+    #[wasm_bindgen(getter, js_name = isCancelled)]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.is_cancelled()
+    }
+}
+
+/// Same as [`prove`], calling `on_progress` with a plain object after each
+/// witness and each folded step, so it can be posted from a worker as is:
+///
+/// - `{ kind: "witness", step, steps, elapsedMs }`
+/// - `{ kind: "step", step, steps, elapsedMs, output }`, `output` being `z_{step+1}`
+///   as decimal strings
+#[wasm_bindgen(js_name = proveWithProgress)]
+pub async fn prove_with_progress(
+    params: &str,
+    r1cs: &[u8],
+    witness_generator_wasm: &[u8],
+    z0: &str,
+    private_inputs: &str,
+    on_progress: Function,
+    cancellation: &ProofCancellation,
+    sym: Option<String>,
+) -> Result<String, WasmError> {
+    let (pp, z0, private_inputs) = parse_prove_inputs(params, z0, private_inputs)?;
+    let inputs = parse_input_signals(r1cs, sym)?;
+    let mut observer = JsProgressObserver {
+        on_progress,
+        steps: private_inputs.len(),
+        step_elapsed: Duration::ZERO,
+    };
+    let recursive_snark = create_recursive_circuit_with_progress(
+        FileLocation::Bytes(witness_generator_wasm.to_vec()),
+        parse_r1cs(r1cs)?,
+        private_inputs,
+        z0,
+        &pp,
+        ProofOptions {
+            inputs: inputs.as_ref(),
+            observer: Some(&mut observer),
+            cancellation: cancellation.0.clone(),
+            ..ProofOptions::default()
+        },
+    )
+    .await
+    .map_err(WasmError::from_proving)?;
+    to_json(&recursive_snark)
+}
+
+/// input signals of the circuit, if its `.sym` file was given
+fn parse_input_signals(
+    r1cs: &[u8],
+    sym: Option<String>,
+) -> Result<Option<InputSignals>, WasmError> {
+    sym.map(|sym| InputSignals::from_bytes(r1cs, &sym))
+        .transpose()
+        .map_err(WasmError::invalid_input)
+}
+
+fn parse_prove_inputs(
+    params: &str,
+    z0: &str,
+    private_inputs: &str,
+) -> Result<(Params, Vec<F1>, Vec<HashMap<String, Value>>), WasmError> {
+    let pp = parse_params(params)?;
+    let z0 = parse_field_elements(z0)?;
+    let private_inputs: Vec<HashMap<String, Value>> = serde_json::from_str(private_inputs)
+        .map_err(|e| WasmError::invalid_input(format!("invalid private inputs: {}", e)))?;
+    if private_inputs.is_empty() {
+        return Err(WasmError::invalid_input("at least one step is required"));
+    }
+    Ok((pp, z0, private_inputs))
+}
+
+struct JsProgressObserver {
+    on_progress: Function,
+    steps: usize,
+    step_elapsed: Duration,
+}
+
+impl JsProgressObserver {
+    fn event(&self, kind: &str, step: usize, elapsed: Duration) -> Object {
+        let event = Object::new();
+        let set = |key: &str, value: JsValue| {
+            let _ = Reflect::set(&event, &JsValue::from_str(key), &value);
+        };
+        set("kind", JsValue::from_str(kind));
+        set("step", JsValue::from(step as u32));
+        set("steps", JsValue::from(self.steps as u32));
+        set("elapsedMs", JsValue::from(elapsed.as_secs_f64() * 1000.0));
+        event
+    }
+
+    fn emit(&self, event: Object) {
+        // a throwing callback must not abort the proof
+        let _ = self.on_progress.call1(&JsValue::NULL, &event);
+    }
+}
+
+impl ProgressObserver for JsProgressObserver {
+    fn witness_generated(&mut self, step: usize, elapsed: Duration) {
+        self.emit(self.event("witness", step, elapsed));
+    }
+
+    fn step_proved(&mut self, _step: usize, elapsed: Duration) {
+        self.step_elapsed = elapsed;
+    }
+
+    fn step_output(&mut self, step: usize, z_i: &[F1]) {
+        let event = self.event("step", step, self.step_elapsed);
+        let output: Array = to_decimal_strings(z_i)
+            .into_iter()
+            .map(|s| JsValue::from_str(&s))
+            .collect();
+        let _ = Reflect::set(&event, &JsValue::from_str("output"), &output);
+        self.emit(event);
+    }
+}
+
+/// Compress a serialized `RecursiveSNARK`. Returns the serialized `CompressedSNARK`.
+#[wasm_bindgen]
+pub fn compress(params: &str, recursive_snark: &str) -> Result<String, WasmError> {
+    let pp = parse_params(params)?;
+    let recursive_snark: RecursiveSNARK<G1, G2, C1, C2> = serde_json::from_str(recursive_snark)
+        .map_err(|e| WasmError::invalid_input(format!("invalid recursive SNARK: {}", e)))?;
+    let compressed_snark = compress_recursive_snark(&pp, &recursive_snark)
+        .map_err(|e| WasmError::new("Proving", format!("{:?}", e)))?;
+    to_json(&compressed_snark)
+}
+
+/// Verify a serialized `CompressedSNARK` of `num_steps` steps starting from `z0`.
+/// Returns the final step output `z_n` as a JSON array of decimal strings.
+#[wasm_bindgen]
+pub fn verify(
+    params: &str,
+    compressed_snark: &str,
+    num_steps: usize,
+    z0: &str,
+) -> Result<String, WasmError> {
+    let pp = parse_params(params)?;
+    let compressed_snark: CompressedSNARK<G1, G2, C1, C2, S1, S2> =
+        serde_json::from_str(compressed_snark)
+            .map_err(|e| WasmError::invalid_input(format!("invalid compressed SNARK: {}", e)))?;
+    let z0 = parse_field_elements(z0)?;
+    let z_n = verify_compressed_proof(&pp, &compressed_snark, num_steps, z0)
+        .map_err(|e| WasmError::new("Verification", e))?;
+    to_json(&to_decimal_strings(&z_n))
+}